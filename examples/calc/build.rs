@@ -0,0 +1,6 @@
+use rlpg::LexerMode;
+
+fn main() {
+    // The lexer reads the UTF-8 bytes of the input
+    rlpg::build::process_file_with("calculator.rlpg", |generator| generator.lexer_mode(LexerMode::Bytes));
+}
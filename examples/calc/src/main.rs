@@ -0,0 +1,29 @@
+// The lexer and parser generated from calculator.rlpg by build.rs.
+// The calculator only uses part of the generated API.
+#[allow(dead_code)]
+mod output {
+    include!(concat!(env!("OUT_DIR"), "/calculator.rs"));
+}
+
+fn main() {
+    println!("Enter an expression: ");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).expect("Failed to readline");
+    let input = input.trim().to_string();
+
+    let tokens = match output::get_tokens(input) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("{}", error.get_err_message());
+            return;
+        },
+    };
+
+    // The grammar computes the value of the expression while parsing
+    let result = output::parse_value(&tokens);
+
+    match result {
+        Ok(value) => println!("Result: {}", value),
+        Err(error) => println!("{}", error.get_err_message()),
+    }
+}
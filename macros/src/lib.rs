@@ -0,0 +1,157 @@
+//! The `rlpg!` macro generates the lexer and parser of a spec at compile time.
+//!
+//! The spec is written inline in the file format:
+//!
+//! ```ignore
+//! mod calculator {
+//!     rlpg_macros::rlpg! {
+//!         SECTION LEXER
+//!         number [0-9]+
+//!
+//!         SECTION GRAMMAR
+//!         root: root "+" number
+//!         | number
+//!         ;
+//!     }
+//! }
+//! ```
+//!
+//! Specs that are not valid Rust tokens, e.g. with a `\` in a regex, can be
+//! given as a string literal instead: `rlpg! { r#"SECTION LEXER ..."# }`.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use rlpg::{Generator, GeneratorError, RlpgErr};
+
+#[proc_macro]
+pub fn rlpg(input: TokenStream) -> TokenStream {
+    let spec = match SpecText::from_tokens(input) {
+        Ok(value) => value,
+        Err(error) => return error,
+    };
+
+    let generated = match Generator::from_text(&spec.text).generate() {
+        Ok(value) => value,
+        Err(GeneratorError::Spec(error)) => {
+            let span = match error.line {
+                Some(line) => spec.get_span(line),
+                None => Span::call_site(),
+            };
+            return compile_error(&format!("{:?}: {}", error.kind, error.get_err_message()), span);
+        },
+        Err(error) => return compile_error(&error.to_string(), Span::call_site()),
+    };
+
+    return match generated.module_source().parse() {
+        Ok(value) => value,
+        Err(error) => compile_error(&format!("The generated parser is not valid Rust: {}", error), Span::call_site()),
+    };
+}
+
+// The text of the spec and the span of the first token on each line
+struct SpecText {
+    text: String,
+    line_spans: Vec<(usize, Span)>,
+}
+
+impl SpecText {
+    fn from_tokens(input: TokenStream) -> Result<SpecText, TokenStream> {
+        let trees: Vec<TokenTree> = input.into_iter().collect();
+
+        // The spec is given as a single string literal
+        if let [TokenTree::Literal(literal)] = trees.as_slice() {
+            let text = match SpecText::parse_string_literal(&literal.to_string()) {
+                Some(value) => value,
+                None => return Err(compile_error("The spec must be a string literal or tokens in the file format", literal.span())),
+            };
+            // Raw strings usually start with a line break after the quote
+            return Ok(SpecText { text: text.trim_start().to_string(), line_spans: vec![(1, literal.span())] });
+        }
+
+        // Rebuild the lines of the spec from the positions of the tokens
+        let mut text = String::new();
+        let mut line_spans = Vec::new();
+        let mut spec_line = 1;
+        let mut prev_end: Option<(usize, usize)> = None;
+        for tree in trees {
+            let span = tree.span();
+            match prev_end {
+                None => line_spans.push((spec_line, span)),
+                Some((line, _)) if span.line() > line => {
+                    // Lines start without indentation since | and ; must begin a line
+                    for _ in line..span.line() {
+                        text.push('\n');
+                        spec_line += 1;
+                    }
+                    line_spans.push((spec_line, span));
+                },
+                Some((_, column)) => {
+                    for _ in column..span.column() {
+                        text.push(' ');
+                    }
+                },
+            }
+
+            let tree_text = span.source_text().unwrap_or_else(|| tree.to_string());
+            spec_line += tree_text.matches('\n').count();
+            text += &tree_text;
+            prev_end = Some((span.end().line(), span.end().column()));
+        }
+        text.push('\n');
+        return Ok(SpecText { text, line_spans });
+    }
+
+    fn parse_string_literal(literal: &str) -> Option<String> {
+        if let Some(raw) = literal.strip_prefix('r') {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            let inner = raw.get(hashes..raw.len() - hashes)?;
+            return Some(inner.strip_prefix('"')?.strip_suffix('"')?.to_string());
+        }
+
+        let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+        let mut text = String::new();
+        let mut chars = inner.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next()? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                'r' => text.push('\r'),
+                '0' => text.push('\0'),
+                // A line continuation skips the whitespace at the start of the next line
+                '\n' => {
+                    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                },
+                other => text.push(other),
+            }
+        }
+        return Some(text);
+    }
+
+    // Returns the span of the first token on the line of the spec
+    fn get_span(&self, line: usize) -> Span {
+        return match self.line_spans.iter().rev().find(|(spec_line, _)| *spec_line <= line) {
+            Some((_, span)) => *span,
+            None => Span::call_site(),
+        };
+    }
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut literal = Literal::string(message);
+    literal.set_span(span);
+    let mut group = Group::new(Delimiter::Brace, TokenStream::from(TokenTree::Literal(literal)));
+    group.set_span(span);
+    let mut punct = Punct::new('!', Spacing::Alone);
+    punct.set_span(span);
+
+    return TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(punct),
+        TokenTree::Group(group),
+    ]);
+}
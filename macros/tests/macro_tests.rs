@@ -0,0 +1,466 @@
+#[allow(warnings)]
+mod calculator {
+    rlpg_macros::rlpg! {
+        SECTION LEXER
+        number [0-9]+
+
+        SECTION GRAMMAR
+        %type <i32> root
+        %left "+" "-"
+
+        root: root "+" root { $$ = $1 + $3; }
+        | root "-" root { $$ = $1 - $3; }
+        | number { $$ = $1.lexeme.parse().unwrap(); }
+        ;
+    }
+}
+
+#[allow(warnings)]
+mod words {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-z]+
+unnamed \ 
+SECTION GRAMMAR
+root: root word
+| word
+;
+"# }
+}
+
+#[test]
+fn test_tokens_spec()
+{
+    let tokens = calculator::get_tokens("10-2+3".to_string()).unwrap();
+    assert!(calculator::parse_value(&tokens).unwrap() == 11);
+}
+
+#[test]
+fn test_expected_terminals()
+{
+    let tokens = calculator::get_tokens("10-+3".to_string()).unwrap();
+    let error = match calculator::parse_value(&tokens) {
+        Err(calculator::ErrorKind::GrammarParseFailed(error)) => error,
+        _ => panic!("expected a syntax error"),
+    };
+    assert!(error.token.lexeme == "+" && error.expected == [calculator::Terminal::Number]);
+    assert!(error.get_err_message() == "Error: expected `number`, found `\"+\"` at 1:4");
+
+    let tokens = calculator::get_tokens("10+3+".to_string()).unwrap();
+    let error = calculator::parse(&tokens).err().unwrap();
+    assert!(error.get_err_message() == "Error: expected `number`, found `eof` at 1:6");
+}
+
+#[test]
+fn test_string_spec()
+{
+    let tokens = words::get_tokens("one two three".to_string()).unwrap();
+    let tree = words::parse(&tokens).unwrap();
+    assert!(tree.children.len() == 2);
+}
+
+#[allow(warnings)]
+mod lines {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-zé]+
+unnamed [\ \n]
+SECTION GRAMMAR
+root: root word
+| word
+;
+"# }
+}
+
+#[test]
+fn test_token_positions()
+{
+    let tokens = lines::get_tokens("one\n  tw\u{e9}\n\nthree".to_string()).unwrap();
+    let positions: Vec<(usize, usize, usize, usize)> = tokens.iter().map(|token| (token.line, token.start_col, token.end_line, token.end_col)).collect();
+    assert!(positions == vec![(1, 1, 1, 4), (2, 3, 2, 6), (4, 1, 4, 6), (4, 6, 4, 6)]);
+
+    // The offsets are in bytes
+    assert!((tokens[1].start_offset, tokens[1].end_offset) == (6, 10));
+    assert!(tokens[3].symbol == lines::Symbol::Terminal(lines::Terminal::Eof));
+
+    // The leaves of the tree keep the positions of their tokens
+    let tree = lines::parse(&tokens).unwrap();
+    assert!(tree.children[1].token.line == 4);
+
+    assert!(matches!(lines::get_tokens("one\ntwo 3".to_string()), Err(lines::ErrorKind::TokenizationFailed(2, 5))));
+}
+
+#[allow(warnings)]
+mod spans {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-z]+
+unnamed [\ \n]
+SECTION GRAMMAR
+root: list
+;
+list: 
+| list optional word
+;
+optional: 
+| "?"
+;
+"# }
+}
+
+#[test]
+fn test_tree_node_spans()
+{
+    let tokens = spans::get_tokens("  one\n? two  ".to_string()).unwrap();
+    let tree = spans::parse(&tokens).unwrap();
+
+    // root spans from the first word to the last word
+    let span = |node: &spans::TreeNode| (node.token.line, node.token.start_col, node.token.end_line, node.token.end_col, node.token.start_offset, node.token.end_offset);
+    assert!(span(&tree) == (1, 3, 2, 6, 2, 11));
+
+    // list: list optional word, where the inner list is list: list optional word
+    let outer = &tree.children[0];
+    let inner = &outer.children[0];
+    assert!(span(inner) == (1, 3, 1, 6, 2, 5));
+    assert!(span(&outer.children[1]) == (2, 1, 2, 2, 6, 7));
+
+    // Empty nodes are at the start of the lookahead
+    let empty_list = &inner.children[0];
+    let empty_optional = &inner.children[1];
+    assert!(empty_list.is_empty() && empty_optional.is_empty());
+    assert!(span(empty_list) == (1, 3, 1, 3, 2, 2));
+    assert!(span(empty_optional) == (1, 3, 1, 3, 2, 2));
+}
+
+#[test]
+fn test_symbol_enums()
+{
+    // Literals are named after their characters
+    let tokens = calculator::get_tokens("1+2-3".to_string()).unwrap();
+    let terminals: Vec<calculator::Terminal> = tokens.iter().map(|token| match token.symbol {
+        calculator::Symbol::Terminal(terminal) => terminal,
+        calculator::Symbol::NonTerminal(_) => panic!("expected a terminal"),
+    }).collect();
+    use calculator::Terminal::{Eof, Minus, Number, Plus};
+    assert!(terminals == vec![Number, Plus, Number, Minus, Number, Eof]);
+    assert!(Plus.name() == "\"+\"" && Number.name() == "number");
+
+    // The nodes of rules carry their rule
+    let tree = spans::parse(&spans::get_tokens("one ? two".to_string()).unwrap()).unwrap();
+    let list = &tree.children[0];
+    assert!(tree.token.symbol == spans::Symbol::NonTerminal(spans::NonTerminal::Root));
+    let rules: Vec<spans::NonTerminal> = list.children.iter().filter_map(|child| match child.token.symbol {
+        spans::Symbol::NonTerminal(rule) => Some(rule),
+        spans::Symbol::Terminal(_) => None,
+    }).collect();
+    assert!(rules == vec![spans::NonTerminal::List, spans::NonTerminal::Optional]);
+    assert!(list.children[1].children[0].token.symbol == spans::Symbol::Terminal(spans::Terminal::Question));
+}
+
+#[allow(warnings)]
+mod statements {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-z]+
+number [0-9]+
+unnamed [\ \n]
+SECTION GRAMMAR
+%type <usize> root list
+root: list { $$ = $1; }
+;
+list: { $$ = 0; }
+| list stmt { $$ = $1 + 1; }
+;
+stmt: word ";"
+| error ";"
+;
+"# }
+}
+
+#[test]
+fn test_error_recovery()
+{
+    let tokens = statements::get_tokens("one; 2 two; three; 3; four;".to_string()).unwrap();
+    let (tree, errors) = statements::parse_with_errors(&tokens);
+
+    // Each bad statement is reported once and parsing continues after its ";"
+    let positions: Vec<(String, usize, usize)> = errors.iter().map(|error| (error.token.lexeme.clone(), error.token.line, error.token.start_col)).collect();
+    assert!(positions == vec![("2".to_string(), 1, 6), ("3".to_string(), 1, 20)]);
+    assert!(tree.is_some());
+    assert!(errors[0].get_err_message() == "Error: expected `eof` or `word`, found `number` at 1:6");
+    assert!(matches!(statements::parse(&tokens), Err(statements::ErrorKind::GrammarParseFailed(error)) if error.token.lexeme == "2"));
+
+    // Typed parsers recover the same way
+    let (value, errors) = statements::parse_value_with_errors(&tokens);
+    assert!(value == Some(5) && errors.len() == 2);
+
+    // The parser cannot recover when the input ends before a ";"
+    let tokens = statements::get_tokens("one; 2 two".to_string()).unwrap();
+    let (tree, errors) = statements::parse_with_errors(&tokens);
+    assert!(tree.is_none() && errors.len() == 1);
+
+    // Errors within three tokens of the last one are not reported again
+    let tokens = statements::get_tokens("2; 3; four;".to_string()).unwrap();
+    let (tree, errors) = statements::parse_with_errors(&tokens);
+    assert!(tree.is_some() && errors.len() == 1);
+}
+
+#[allow(warnings)]
+mod marked_statements {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-z]+
+number [0-9]+
+unnamed [\ \n]
+SECTION GRAMMAR
+root: list
+;
+list:
+| list stmt
+;
+stmt: word ";"
+| word ";" "!"
+| error ";"
+;
+"# }
+}
+
+#[test]
+fn test_expected_terminals_before_error_reductions()
+{
+    // The state after `one;` reduces on the error token, but the error lists what it could have shifted
+    let tokens = marked_statements::get_tokens("one; 2;".to_string()).unwrap();
+    let (tree, errors) = marked_statements::parse_with_errors(&tokens);
+    assert!(tree.is_some() && errors.len() == 1);
+    assert!(errors[0].get_err_message() == "Error: expected `\"!\"`, `eof` or `word`, found `number` at 1:6");
+}
+
+#[test]
+fn test_lexer_error_recovery()
+{
+    let (tokens, errors) = statements::get_tokens_with_errors("one; t#@o; three;\n$".to_string());
+
+    // Each run of bad characters is one error token
+    let positions: Vec<(String, usize, usize)> = errors.iter().map(|error| (error.token.lexeme.clone(), error.token.line, error.token.start_col)).collect();
+    assert!(positions == vec![("#@".to_string(), 1, 7), ("$".to_string(), 2, 1)]);
+    assert!(errors[0].get_err_message() == "Error: unable to tokenize \"#@\" at 1:7");
+    let names: Vec<&str> = tokens.iter().map(|token| token.symbol.name()).collect();
+    assert!(names == vec!["word", "\";\"", "word", "error", "word", "\";\"", "word", "\";\"", "error", "eof"]);
+
+    // The parser recovers from error tokens with the error productions
+    let (tokens, _) = statements::get_tokens_with_errors("one; t#@o; three;".to_string());
+    let (value, errors) = statements::parse_value_with_errors(&tokens);
+    assert!(value == Some(3) && errors.len() == 1);
+
+    assert!(matches!(statements::get_tokens("one;\n t#o;".to_string()), Err(statements::ErrorKind::TokenizationFailed(2, 3))));
+}
+
+#[allow(warnings)]
+mod longest_match {
+    rlpg_macros::rlpg! {
+        SECTION LEXER
+        short a
+        long abc
+        letter [a-z]
+        other .
+
+        SECTION GRAMMAR
+        root: root part
+        | part
+        ;
+        part: short
+        | long
+        | letter
+        | other
+        ;
+    }
+}
+
+#[test]
+fn test_longest_match()
+{
+    // The lexer backs up to the end of "a" when "ab" cannot be extended to "abc"
+    let tokens = longest_match::get_tokens("abcabab".to_string()).unwrap();
+    let lexemes: Vec<(&str, &str)> = tokens.iter().map(|token| (token.symbol.name(), token.lexeme.as_str())).collect();
+    assert!(lexemes == vec![("long", "abc"), ("short", "a"), ("letter", "b"), ("short", "a"), ("letter", "b"), ("eof", "")]);
+    assert!(tokens[2].start_col == 5);
+    assert!(longest_match::parse(&tokens).is_ok());
+
+    // Characters without a rule of their own match the wildcard
+    let tokens = longest_match::get_tokens("b?é".to_string()).unwrap();
+    let names: Vec<&str> = tokens.iter().map(|token| token.symbol.name()).collect();
+    assert!(names == vec!["letter", "other", "other", "eof"]);
+}
+
+#[test]
+fn test_streaming()
+{
+    // Tokens are read lazily, so the input does not have to end
+    let lexer = lines::Lexer::from_chars("one two\n".chars().cycle());
+    let lexemes: Vec<String> = lexer.take(5).map(|token| token.unwrap().lexeme).collect();
+    assert!(lexemes == vec!["one", "two", "one", "two", "one"]);
+
+    // Readers are decoded as UTF-8 and have the same positions as strings
+    let input = "one\n  tw\u{e9}\n\nthree";
+    let mut lexer = lines::Lexer::from_reader(input.as_bytes());
+    let tokens: Vec<lines::Token> = lexer.by_ref().map(|token| token.unwrap()).collect();
+    assert!(tokens == lines::get_tokens(input.to_string()).unwrap());
+    assert!(lexer.errors().is_empty());
+
+    let mut errors = Vec::new();
+    let tree = lines::parse_stream(lines::Lexer::from_reader(input.as_bytes()), &mut errors).unwrap();
+    assert!(tree.children[1].token.lexeme == "three" && errors.is_empty());
+
+    let mut errors = Vec::new();
+    let value = calculator::parse_value_stream(calculator::Lexer::from_reader("10-2+3".as_bytes()), &mut errors);
+    assert!(value.unwrap() == 11);
+
+    // Input that is not UTF-8 stops the lexer and the parser
+    let mut lexer = lines::Lexer::from_reader(&b"one \xff two"[..]);
+    assert!(lexer.next().unwrap().unwrap().lexeme == "one");
+    assert!(matches!(lexer.next(), Some(Err(lines::ErrorKind::ReadFailed(std::io::ErrorKind::InvalidData)))));
+    assert!(lexer.next().is_none());
+    let result = lines::parse_stream(lines::Lexer::from_reader(&b"one \xff two"[..]), &mut Vec::new());
+    assert!(matches!(result, Err(lines::ErrorKind::ReadFailed(_))));
+}
+
+#[allow(warnings)]
+mod syntax_tree {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-z]+
+number [0-9]+
+unnamed [\ \n]
+SECTION GRAMMAR
+%ast
+root: list
+;
+list:
+| list stmt
+;
+stmt: word "=" number ";"
+| "print" word ";"
+| error ";"
+;
+"# }
+}
+
+#[test]
+fn test_syntax_tree()
+{
+    use syntax_tree::ast::{List, Root, Stmt};
+
+    let tokens = syntax_tree::get_tokens("x = 1; print x;".to_string()).unwrap();
+    let Root::List(list) = syntax_tree::parse_ast(&tokens).unwrap();
+    let (list, second) = match *list {
+        List::ListStmt(list, stmt) => (list, stmt),
+        List::Empty => panic!("expected two statements"),
+    };
+    assert!(matches!(*second, Stmt::PrintWordSemicolon(_, ref word, _) if word.lexeme == "x"));
+    let first = match *list {
+        List::ListStmt(list, stmt) if *list == List::Empty => stmt,
+        _ => panic!("expected one statement"),
+    };
+    match *first {
+        Stmt::WordEqualNumberSemicolon(name, _, value, _) => assert!(name.lexeme == "x" && value.lexeme == "1"),
+        _ => panic!("expected an assignment"),
+    }
+
+    // The error token is a field of the production that recovers from it
+    let tokens = syntax_tree::get_tokens("x 1; y = 2;".to_string()).unwrap();
+    let (root, errors) = syntax_tree::parse_ast_with_errors(&tokens);
+    assert!(errors.len() == 1);
+    let Root::List(list) = root.unwrap();
+    let List::ListStmt(list, _) = *list else { panic!("expected two statements") };
+    assert!(matches!(*list, List::ListStmt(_, ref stmt) if matches!(**stmt, Stmt::ErrorSemicolon(..))));
+    assert!(syntax_tree::parse_ast(&tokens).is_err());
+}
+
+mod ebnf {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+word [a-z]+
+number [0-9]+
+unnamed [\ \n]
+SECTION GRAMMAR
+%ast
+root: stmt*
+;
+stmt: "return" expr? ";"
+| word "(" args? ")" ";"
+| "print" (number | word)+ ";"
+;
+args: expr ("," expr)*
+;
+expr: number
+| word
+;
+"# }
+}
+
+#[test]
+fn test_ebnf_operators()
+{
+    use ebnf::ast::{Args, CommaExpr, Expr, NumberOrWord, Root, Stmt};
+
+    // The items of a repetition are the children of one node
+    let tokens = ebnf::get_tokens("f(a, 1, b); print 1 x;".to_string()).unwrap();
+    let tree = ebnf::parse(&tokens).unwrap();
+    let statements = &tree.children[0];
+    assert!(statements.token.lexeme == "stmt*" && statements.children.len() == 2);
+    let args = &statements.children[0].children[2].children[0];
+    assert!(args.token.symbol == ebnf::Symbol::NonTerminal(ebnf::NonTerminal::Args));
+    let rest = &args.children[1];
+    assert!(rest.children.len() == 2 && rest.children.iter().all(|child| child.token.lexeme == "(\",\" expr)"));
+    assert!(rest.token.start_offset == 3 && rest.token.end_offset == 9);
+
+    let Root::StmtStar(statements) = ebnf::parse_ast(&tokens).unwrap();
+    match &statements[0] {
+        Stmt::WordLParenArgsOptRParenSemicolon(name, _, Some(args), _, _) => {
+            assert!(name.lexeme == "f");
+            let Args::ExprCommaExprStar(first, rest) = &**args;
+            assert!(matches!(**first, Expr::Word(ref word) if word.lexeme == "a"));
+            let rest: Vec<&str> = rest.iter().map(|CommaExpr::CommaExpr(_, expr)| match &**expr {
+                Expr::Number(token) | Expr::Word(token) => token.lexeme.as_str(),
+            }).collect();
+            assert_eq!(rest, vec!["1", "b"]);
+        },
+        _ => panic!("expected a call with arguments"),
+    }
+    match &statements[1] {
+        Stmt::PrintNumberOrWordPlusSemicolon(_, items, _) => assert!(matches!(items.as_slice(), [NumberOrWord::Number(_), NumberOrWord::Word(_)])),
+        _ => panic!("expected a print"),
+    }
+
+    // Missing optional parts are None and a missing list is empty
+    let tokens = ebnf::get_tokens("return; g();".to_string()).unwrap();
+    let Root::StmtStar(statements) = ebnf::parse_ast(&tokens).unwrap();
+    assert!(matches!(statements[0], Stmt::ReturnExprOptSemicolon(_, None, _)));
+    assert!(matches!(statements[1], Stmt::WordLParenArgsOptRParenSemicolon(_, _, None, _, _)));
+    let tokens = ebnf::get_tokens("".to_string()).unwrap();
+    assert!(ebnf::parse_ast(&tokens).unwrap() == Root::StmtStar(Vec::new()));
+}
+
+mod ebnf_values {
+    rlpg_macros::rlpg! { r#"
+SECTION LEXER
+number [0-9]+
+unnamed [\ \n]
+SECTION GRAMMAR
+%type <i64> root value
+root: "-"? value ("," value)* ";"+ { $$ = $3.iter().fold($2, |sum, (_, value)| sum + value) * if $1.is_some() { -1 } else { 1 }; }
+;
+value: number { $$ = $1.lexeme.parse().unwrap(); }
+;
+"# }
+}
+
+#[test]
+fn test_ebnf_values()
+{
+    // x? is an Option, x* is a Vec, and ("," value) is a tuple of a token and a value
+    let tokens = ebnf_values::get_tokens("- 1, 2, 3;;".to_string()).unwrap();
+    assert_eq!(ebnf_values::parse_value(&tokens).unwrap(), -6);
+    let tokens = ebnf_values::get_tokens("4;".to_string()).unwrap();
+    assert_eq!(ebnf_values::parse_value(&tokens).unwrap(), 4);
+}
@@ -0,0 +1,83 @@
+//! Helpers for generating the lexer and parser from a build script.
+//!
+//! ```no_run
+//! // build.rs
+//! fn main() {
+//!     rlpg::build::process_file("src/calculator.rlpg");
+//! }
+//! ```
+//!
+//! The generated module is written to `OUT_DIR` with the name of the spec
+//! and can be included with
+//! `include!(concat!(env!("OUT_DIR"), "/calculator.rs"));`.
+
+use std::path::{Path, PathBuf};
+
+use crate::generator::{Generator, GeneratorError};
+use crate::grammar2::TableKind;
+
+/// Generates the module for the spec at the path, which is relative to the
+/// crate being built, and tells cargo to rerun the build script when the
+/// spec changes. Errors in the spec fail the build with a readable message.
+pub fn process_file(path: &str)
+{
+    process_file_with_table(path, TableKind::LR1);
+}
+
+pub fn process_file_with_table(path: &str, kind: TableKind)
+{
+    process_file_with(path, |generator| generator.table(kind));
+}
+
+/// Like `process_file` but configures the generator for the spec, e.g.
+/// `process_file_with(path, |generator| generator.lexer_mode(LexerMode::Bytes))`.
+pub fn process_file_with(path: &str, configure: impl FnOnce(Generator) -> Generator)
+{
+    if let Err(error) = compile_with(path, configure) {
+        eprintln!("error: failed to generate a parser from {}", path);
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+/// Writes the module for the spec to `OUT_DIR` and returns the path of the module.
+pub fn compile(path: &str, kind: TableKind) -> Result<PathBuf, GeneratorError>
+{
+    return compile_with(path, |generator| generator.table(kind));
+}
+
+/// Writes the module for the spec to `OUT_DIR` with the generator configured by the function.
+pub fn compile_with(path: &str, configure: impl FnOnce(Generator) -> Generator) -> Result<PathBuf, GeneratorError>
+{
+    let out_dir = match std::env::var("OUT_DIR") {
+        Ok(value) => value,
+        Err(_) => return Err(GeneratorError::Output(std::io::Error::new(std::io::ErrorKind::NotFound, "OUT_DIR is only set when running a build script"))),
+    };
+    return compile_to_with(path, Path::new(&out_dir), configure);
+}
+
+/// Like `compile` but writes the module to the directory instead of `OUT_DIR`.
+pub fn compile_to(path: &str, out_dir: &Path, kind: TableKind) -> Result<PathBuf, GeneratorError>
+{
+    return compile_to_with(path, out_dir, |generator| generator.table(kind));
+}
+
+// The generator always reads the spec at the path, so the module and the
+// rerun-if-changed line are named after the spec that was generated
+fn compile_to_with(path: &str, out_dir: &Path, configure: impl FnOnce(Generator) -> Generator) -> Result<PathBuf, GeneratorError>
+{
+    println!("cargo:rerun-if-changed={}", path);
+
+    let file_name = match Path::new(path).file_stem() {
+        Some(value) => value.to_string_lossy().to_string(),
+        None => "parser".to_string(),
+    };
+    let output = out_dir.join(format!("{}.rs", file_name));
+
+    let generated = configure(Generator::from_file(path)).generate()?;
+    // The module is included in another crate, so it has no main function
+    if let Err(error) = std::fs::write(&output, generated.module_source()) {
+        return Err(GeneratorError::Output(error));
+    }
+    return Ok(output);
+}
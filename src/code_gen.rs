@@ -1,13 +1,23 @@
-use std::{any, io::Write, fmt::format};
+use std::{any, io::Write, fmt::format, collections::{HashMap, BTreeMap, BTreeSet}};
 
-use crate::{table_dfa_builder::TableDFA, nfa::TransitionKind, token::Token, grammar2::GrammarGenerator};
+use crate::{table_dfa_builder::{LexerMode, TableDFA, TransitionStyle}, nfa::TransitionKind, grammar2::{GrammarGenerator, GrammarRule, HelperKind, Production}};
 use std::fs::File;
 
 pub struct CodeGen
 {
     pub table: TableDFA,
+    pub lexer_mode: LexerMode,
+    pub transition_style: TransitionStyle,
     pub curr_state_name: String,
     pub grammar_gen: GrammarGenerator,
+    // Maps the priority of each lexer rule to its action code
+    pub lexer_actions: HashMap<i32, String>,
+    // Maps grammar rule names to the Rust type of their semantic value
+    pub value_types: HashMap<String, String>,
+    // True if the typed syntax tree is generated
+    pub ast: bool,
+    // Code from the code section of the input file
+    pub code: Option<String>,
 }
 
 impl CodeGen {
@@ -26,131 +36,631 @@ impl CodeGen {
         return Ok(());
     }
 
-    pub fn generate_lexer(&mut self, path: &str) -> std::io::Result<()>
+    pub fn generate_lexer(&self, path: &str) -> std::io::Result<()>
     {
-        let mut text = "use std::collections::HashMap;\n".to_string();
+        return CodeGen::write_to_file(path, self.generate_source());
+    }
+
+    // Returns the source of the generated lexer and parser
+    pub fn generate_source(&self) -> String
+    {
+        return self.generate_module() + &self.create_main_fn();
+    }
+
+    // Returns the source of the generated lexer and parser without the main function
+    pub fn generate_module(&self) -> String
+    {
+        let mut text = String::new();
+        if let Some(code) = &self.code {
+            text += code;
+            text += "\n";
+        }
         text += "\n\n";
 
         text += &self.create_structs_and_enums();
         text += "\n";
 
-        text += &self.create_transition_kind();
-        text += "\n";
         text += &self.create_check_accepting_state_function();
         text += "\n";
-        text += &self.create_transition_function();
+        match self.lexer_mode {
+            LexerMode::Chars => {
+                text += &self.create_transition_kind();
+                text += "\n";
+                text += &match self.transition_style {
+                    TransitionStyle::Table => self.create_char_table_transition_function(),
+                    TransitionStyle::Branches => self.create_transition_function(),
+                };
+                text += "\n";
+                text += &self.create_char_units();
+                text += "\n";
+            },
+            LexerMode::Bytes => {
+                text += &match self.transition_style {
+                    TransitionStyle::Table => self.create_byte_table_transition_function(),
+                    TransitionStyle::Branches => self.create_byte_transition_function(),
+                };
+                text += "\n";
+                text += &self.create_byte_units();
+                text += "\n";
+            },
+        }
+        text += &self.create_lexer_action_function();
+        text += "\n";
+        text += &self.create_reduce_action_function();
         text += "\n";
-        text += &self.create_get_action_table_function();
+        text += &self.create_extends_list_function();
         text += "\n";
-        text += &self.create_get_goto_table_function();
+        text += &self.create_parse_tables();
+        text += "\n";
+        text += &self.create_get_expected_terminals_function();
         text += "\n";
         text += &self.create_parse_function();
         text += "\n";
         text += &self.create_grammar_parse_function();
         text += "\n";
-        text += &self.create_main_fn();
-        
-        return CodeGen::write_to_file(path, text);
+        if self.is_typed() {
+            text += &self.create_semantic_value_enum();
+            text += "\n";
+            text += &self.create_reduce_value_function();
+            text += "\n";
+            text += &self.create_parse_value_function();
+            text += "\n";
+        }
+        if self.ast {
+            text += &self.create_ast_types();
+            text += "\n";
+            text += &self.create_reduce_ast_function();
+            text += "\n";
+            text += &self.create_parse_ast_function();
+            text += "\n";
+        }
+        return text;
     }
 
     fn create_main_fn(&self) -> String
     {
-        let mut main = "pub fn main()
-        {{
-            println!(\"Enter a string to match: \");
-            let mut to_check = String::new();
-            std::io::stdin().read_line(&mut to_check).expect(\"failed to readline\");
-            let to_check = to_check.trim().to_string();
-            let result = get_tokens(to_check);
-            //println!(\"{:?}\", result);
-            if let Err(err) = result {
-                println!(\"Error\");
-                return;
+        stringify!(
+            pub fn main()
+            {
+                println!("Enter the text to parse, followed by end of file: ");
+
+                // The input is tokenized while it is parsed
+                let mut lexer = Lexer::from_reader(std::io::stdin().lock());
+                let mut errors = Vec::new();
+                let result = parse_stream(lexer.by_ref(), &mut errors);
+
+                for error in lexer.errors() {
+                    println!("{}", error.get_err_message());
+                }
+                for error in &errors {
+                    println!("{}", error.get_err_message());
+                }
+                match result {
+                    Ok(_) if errors.is_empty() && lexer.errors().is_empty() => println!("The input was parsed successfully"),
+                    Ok(_) => (),
+                    Err(ErrorKind::GrammarParseFailed(_)) => println!("Error: unable to recover from the syntax errors"),
+                    Err(error) => println!("{}", error.get_err_message()),
+                }
+            }
+        ).to_string()
+    }
+
+    pub fn create_parse_function(&self) -> String
+    {
+        stringify!(
+            // Reads the input lazily and produces tokens as they are needed.
+            // Only the input of the lexeme being matched is buffered.
+            pub struct Lexer<'a> {
+                input: Box<dyn Iterator<Item = Result<LexerUnit, ErrorKind>> + 'a>,
+                // Input that was read while looking for the longest lexeme but is not part of a token yet
+                buffer: std::collections::VecDeque<LexerUnit>,
+                errors: Vec<LexicalError>,
+                // Line, column and byte offset of the start of the next lexeme
+                line: usize,
+                column: usize,
+                offset: usize,
+                finished: bool,
             }
 
-            let mut result = result.unwrap();
-            let eof_token = Token::new(\"eof\".to_string(), 0, 0, Symbol::eof_symbol());
-            result.push(eof_token);
+            impl<'a> Lexer<'a> {
+                pub fn from_chars(chars: impl Iterator<Item = char> + 'a) -> Lexer<'a>
+                {
+                    Lexer::new(chars_to_units(chars))
+                }
+
+                // The input must be UTF-8
+                pub fn from_reader(reader: impl std::io::Read + 'a) -> Lexer<'a>
+                {
+                    Lexer::new(reader_to_units(reader))
+                }
+
+                fn new(input: Box<dyn Iterator<Item = Result<LexerUnit, ErrorKind>> + 'a>) -> Lexer<'a>
+                {
+                    Lexer { input: input, buffer: std::collections::VecDeque::new(), errors: Vec::new(), line: 1, column: 1, offset: 0, finished: false }
+                }
+
+                // The characters that could not be tokenized so far
+                pub fn errors(&self) -> &[LexicalError]
+                {
+                    &self.errors
+                }
+
+                // Returns the unit at the index of the buffer, reading from the input if needed
+                fn peek(&mut self, index: usize) -> Result<Option<LexerUnit>, ErrorKind>
+                {
+                    while self.buffer.len() <= index {
+                        match self.input.next() {
+                            Some(c) => self.buffer.push_back(c?),
+                            None => return Ok(None),
+                        }
+                    }
+                    Ok(Some(self.buffer[index]))
+                }
+
+                // Follows the transitions until the lexeme cannot be extended and returns
+                // the last accepting state with its rule and the length of the longest lexeme
+                fn longest_match(&mut self) -> Result<Option<(i32, Option<Terminal>, usize)>, ErrorKind>
+                {
+                    let mut curr_state = 1;
+                    let mut length = 0;
+                    let mut last_accepting = None;
+                    while let Some(unit) = self.peek(length)? {
+                        match unit_transition(curr_state, unit) {
+                            Some(next_state) => {
+                                curr_state = next_state;
+                                length += 1;
+                                if let Some(terminal) = is_accepting(curr_state) {
+                                    last_accepting = Some((curr_state, terminal, length));
+                                }
+                            },
+                            None => break,
+                        }
+                    }
+                    Ok(last_accepting)
+                }
+
+                // Removes the lexeme from the buffer and moves the position to its end
+                fn take_lexeme(&mut self, length: usize) -> String
+                {
+                    let units: Vec<LexerUnit> = self.buffer.drain(..length).collect();
+                    let lexeme = units_to_string(&units);
+                    for c in lexeme.chars() {
+                        if c == '\n' {
+                            self.line += 1;
+                            self.column = 1;
+                        } else {
+                            self.column += 1;
+                        }
+                    }
+                    // The lexeme of input that is not UTF-8 is longer than the input
+                    self.offset += units_len_utf8(&units);
+                    lexeme
+                }
+
+                // Removes the lexeme from the buffer and creates its token
+                fn take_token(&mut self, length: usize, terminal: Terminal) -> Token
+                {
+                    let (line, column, offset) = (self.line, self.column, self.offset);
+                    let lexeme = self.take_lexeme(length);
+                    Token { lexeme: lexeme, line: line, start_col: column, end_line: self.line, end_col: self.column, start_offset: offset, end_offset: self.offset, symbol: Symbol::Terminal(terminal) }
+                }
+
+                // Removes one character from the buffer and returns its lexeme
+                fn take_error_char(&mut self) -> Result<String, ErrorKind>
+                {
+                    let mut length = 1;
+                    while length < self.buffer[0].char_len() && self.peek(length)?.map_or(false, |unit| unit.is_continuation()) {
+                        length += 1;
+                    }
+                    Ok(self.take_lexeme(length))
+                }
+
+                fn next_token(&mut self) -> Result<Option<Token>, ErrorKind>
+                {
+                    loop {
+                        if self.peek(0)?.is_none() {
+                            if self.finished {
+                                return Ok(None);
+                            }
+                            self.finished = true;
+                            return Ok(Some(Token::new(String::new(), Symbol::Terminal(Terminal::Eof), self.line, self.column, self.offset)));
+                        }
 
-        ".to_string();
+                        match self.longest_match()? {
+                            Some((state, Some(terminal), length)) => {
+                                let token = self.take_token(length, terminal);
+                                run_lexer_action(state, &token.lexeme);
+                                return Ok(Some(token));
+                            },
+                            // Unnamed rules do not produce tokens
+                            Some((state, None, length)) => {
+                                let lexeme = self.take_lexeme(length);
+                                run_lexer_action(state, &lexeme);
+                            },
+                            None => {
+                                // Skip the characters that no lexeme can start with, which become one error token.
+                                // The position moves with each character, so the token is only built at the end.
+                                let (line, column, offset) = (self.line, self.column, self.offset);
+                                let mut lexeme = self.take_error_char()?;
+                                while self.peek(0)?.is_some() && self.longest_match()?.is_none() {
+                                    lexeme.push_str(&self.take_error_char()?);
+                                }
+                                let token = Token { lexeme: lexeme, line: line, start_col: column, end_line: self.line, end_col: self.column, start_offset: offset, end_offset: self.offset, symbol: Symbol::Terminal(Terminal::Error) };
+                                self.errors.push(LexicalError { token: token.clone() });
+                                return Ok(Some(token));
+                            },
+                        }
+                    }
+                }
+            }
 
-        //main += &format!("let action_table = {}", self.create_action_table());
+            impl<'a> Iterator for Lexer<'a> {
+                type Item = Result<Token, ErrorKind>;
 
-        //main += &format!("let goto_table = {}", self.create_goto_table());
+                fn next(&mut self) -> Option<Result<Token, ErrorKind>>
+                {
+                    match self.next_token() {
+                        Ok(token) => token.map(Ok),
+                        Err(error) => {
+                            // The input cannot be read after an error
+                            self.finished = true;
+                            self.buffer.clear();
+                            self.input = Box::new(std::iter::empty());
+                            Some(Err(error))
+                        },
+                    }
+                }
+            }
 
-        main += "let grammar_result = parse(&result);";
-        main += "println!(\"Result: {}\", grammar_result.is_err())";
+            // Tokenizes the text and returns every token along with the characters that could not be tokenized.
+            // Each run of such characters becomes a token named error, so the parser can recover from it.
+            pub fn get_tokens_with_errors(text: String) -> (Vec<Token>, Vec<LexicalError>)
+            {
+                let mut lexer = Lexer::new(text_to_units(&text));
+                let mut tokens: Vec<Token> = Vec::new();
+                // A string is always read successfully
+                while let Some(Ok(token)) = lexer.next() {
+                    tokens.push(token);
+                }
+                let errors = lexer.errors().to_vec();
+                (tokens, errors)
+            }
 
-        main += "}}";
-        return main;
+            pub fn get_tokens(text: String) -> Result<Vec<Token>, ErrorKind>
+            {
+                let (tokens, errors) = get_tokens_with_errors(text);
+                match errors.first() {
+                    Some(error) => Err(ErrorKind::TokenizationFailed(error.token.line, error.token.start_col)),
+                    None => Ok(tokens),
+                }
+            }
+        ).to_string()
     }
 
-    pub fn create_parse_function(&mut self) -> String
+    // Lexing functions for the units of the input when the lexer reads characters
+    fn create_char_units(&self) -> String
     {
         stringify!(
-            pub fn get_tokens(text: String) -> Result<Vec<Token>, ErrorKind>
+            type LexerUnit = char;
+
+            trait Utf8Unit {
+                // The number of units of the character that starts with this unit
+                fn char_len(&self) -> usize;
+                fn is_continuation(&self) -> bool;
+            }
+
+            impl Utf8Unit for char {
+                fn char_len(&self) -> usize { 1 }
+                fn is_continuation(&self) -> bool { false }
+            }
+
+            fn unit_transition(curr: i32, unit: char) -> Option<i32>
             {
-                let mut curr_state = 1;
-                let seq: Vec<char> = text.chars().collect();
-                let mut index = 0;
-                let mut tokens: Vec<Token> = Vec::new();
+                transition(curr, TransitionKind::Character(unit))
+            }
 
-                // Used to keep track of lexeme info
-                let mut start_col = 0;
-                let mut end_col = 0;
-
-                while index < seq.len() {
-                    // Check if accepting state
-                    
-                    // Perform transition or error
-                    let trans_kind = TransitionKind::Character(seq[index]);
-                    if let Some(next_state) = transition(curr_state, trans_kind) {
-                        curr_state = next_state;
-                        end_col += 1;
-                    } else {
-                        // if current state is accepting
-                        if let Some(token) = is_accepting(curr_state)
-                        {
-                            //println!("token index {} {}", index, curr_state);
-                            if !token.is_empty() {
-                                let sym = Symbol {name: token.to_string(), is_terminal: true};
-                                let lexeme = text[start_col..end_col].to_string();
-                                let token = Token::new(lexeme, start_col, end_col - 1, sym);
-                                tokens.push(token);
-                            }
-                            curr_state = 1;
-                            start_col = index;
-                            end_col = index;
-                            index -= 1;
-                        }
-                        else {
-                            println!("tokens: {:?}", tokens);
-                            println!("here");
-                            return Err(ErrorKind::TokenizationFailed(start_col, end_col));
-                        }
+            fn units_to_string(units: &[char]) -> String
+            {
+                units.iter().collect()
+            }
+
+            fn units_len_utf8(units: &[char]) -> usize
+            {
+                units.iter().map(|c| c.len_utf8()).sum()
+            }
+
+            fn chars_to_units<'a>(chars: impl Iterator<Item = char> + 'a) -> Box<dyn Iterator<Item = Result<char, ErrorKind>> + 'a>
+            {
+                Box::new(chars.map(Ok))
+            }
+
+            fn text_to_units<'a>(text: &'a str) -> Box<dyn Iterator<Item = Result<char, ErrorKind>> + 'a>
+            {
+                Box::new(text.chars().map(Ok))
+            }
+
+            fn reader_to_units<'a>(reader: impl std::io::Read + 'a) -> Box<dyn Iterator<Item = Result<char, ErrorKind>> + 'a>
+            {
+                Box::new(ReaderChars { bytes: std::io::Read::bytes(std::io::BufReader::new(reader)) })
+            }
+
+            // Decodes the UTF-8 bytes of a reader into characters
+            struct ReaderChars<R: std::io::Read> {
+                bytes: std::io::Bytes<std::io::BufReader<R>>,
+            }
+
+            impl<R: std::io::Read> Iterator for ReaderChars<R> {
+                type Item = Result<char, ErrorKind>;
+
+                fn next(&mut self) -> Option<Result<char, ErrorKind>>
+                {
+                    let mut bytes = [0; 4];
+                    bytes[0] = match self.bytes.next()? {
+                        Ok(byte) => byte,
+                        Err(error) => return Some(Err(ErrorKind::ReadFailed(error.kind()))),
+                    };
+                    let length = match bytes[0].leading_ones() {
+                        0 => 1,
+                        value @ 2..=4 => value as usize,
+                        _ => return Some(Err(ErrorKind::ReadFailed(std::io::ErrorKind::InvalidData))),
+                    };
+                    for i in 1..length {
+                        bytes[i] = match self.bytes.next() {
+                            Some(Ok(byte)) => byte,
+                            Some(Err(error)) => return Some(Err(ErrorKind::ReadFailed(error.kind()))),
+                            None => return Some(Err(ErrorKind::ReadFailed(std::io::ErrorKind::UnexpectedEof))),
+                        };
+                    }
+                    match std::str::from_utf8(&bytes[..length]) {
+                        Ok(text) => text.chars().next().map(Ok),
+                        Err(_) => Some(Err(ErrorKind::ReadFailed(std::io::ErrorKind::InvalidData))),
                     }
-                    index += 1;
                 }
+            }
+        ).to_string()
+    }
+
+    // Lexing functions for the units of the input when the lexer reads bytes
+    fn create_byte_units(&self) -> String
+    {
+        stringify!(
+            type LexerUnit = u8;
+
+            trait Utf8Unit {
+                // The number of units of the character that starts with this unit
+                fn char_len(&self) -> usize;
+                fn is_continuation(&self) -> bool;
+            }
 
-                //println!("token index {} {}", index, curr_state);
-                if let Some(token) = is_accepting(curr_state)
+            impl Utf8Unit for u8 {
+                fn char_len(&self) -> usize
                 {
-                    if !token.is_empty() {
-                        let sym = Symbol {name: token.to_string(), is_terminal: true};
-                        let lexeme = text[start_col..end_col].to_string();
-                        let token = Token::new(lexeme, start_col, end_col - 1, sym);
-                        tokens.push(token);
+                    match self.leading_ones() {
+                        value @ 2..=4 => value as usize,
+                        _ => 1,
                     }
                 }
-                let eof_token = Token::new("eof".to_string(), end_col, end_col, Symbol::eof_symbol());
-                tokens.push(eof_token);
-                Ok(tokens)
+
+                fn is_continuation(&self) -> bool
+                {
+                    self.leading_ones() == 1
+                }
+            }
+
+            fn unit_transition(curr: i32, unit: u8) -> Option<i32>
+            {
+                transition(curr, unit)
+            }
+
+            // Lexemes are whole characters, so only error tokens can contain bytes that are not UTF-8
+            fn units_to_string(units: &[u8]) -> String
+            {
+                String::from_utf8_lossy(units).into_owned()
+            }
+
+            fn units_len_utf8(units: &[u8]) -> usize
+            {
+                units.len()
             }
 
+            fn chars_to_units<'a>(chars: impl Iterator<Item = char> + 'a) -> Box<dyn Iterator<Item = Result<u8, ErrorKind>> + 'a>
+            {
+                Box::new(chars.flat_map(|c| {
+                    let mut bytes = [0; 4];
+                    let len = c.encode_utf8(&mut bytes).len();
+                    IntoIterator::into_iter(bytes).take(len)
+                }).map(Ok))
+            }
+
+            fn text_to_units<'a>(text: &'a str) -> Box<dyn Iterator<Item = Result<u8, ErrorKind>> + 'a>
+            {
+                Box::new(text.bytes().map(Ok))
+            }
+
+            fn reader_to_units<'a>(reader: impl std::io::Read + 'a) -> Box<dyn Iterator<Item = Result<u8, ErrorKind>> + 'a>
+            {
+                Box::new(std::io::Read::bytes(std::io::BufReader::new(reader)).map(|byte| byte.map_err(|error| ErrorKind::ReadFailed(error.kind()))))
+            }
         ).to_string()
     }
 
-    pub fn create_transition_kind(&mut self) -> String 
+    // Characters with the same destination in every state share a class, and the
+    // characters that have no transitions of their own are in class 0
+    fn create_char_table_transition_function(&self) -> String
+    {
+        let mut states: Vec<i32> = self.table.transitions.keys().cloned().collect();
+        states.sort();
+        let mut chars: Vec<char> = self.table.transitions.values()
+            .flat_map(|transitions| transitions.keys())
+            .filter_map(|kind| match kind { TransitionKind::Character(c) => Some(*c), _ => None })
+            .collect();
+        chars.sort();
+        chars.dedup();
+
+        let destinations = |c: Option<char>| -> Vec<Option<i32>> {
+            states.iter().map(|state| {
+                let transitions = &self.table.transitions[state];
+                c.and_then(|c| transitions.get(&TransitionKind::Character(c))).or(transitions.get(&TransitionKind::AnyChar)).cloned()
+            }).collect()
+        };
+
+        let mut classes: Vec<Vec<Option<i32>>> = vec![destinations(None)];
+        let mut ranges: Vec<(char, char, usize)> = Vec::new();
+        for c in chars {
+            let row = destinations(Some(c));
+            let class = match classes.iter().position(|class| *class == row) {
+                Some(value) => value,
+                None => {
+                    classes.push(row);
+                    classes.len() - 1
+                },
+            };
+            match ranges.last_mut() {
+                Some(last) if last.2 == class && last.1 as u32 + 1 == c as u32 => last.1 = c,
+                _ if class == 0 => (),
+                _ => ranges.push((c, c, class)),
+            }
+        }
+
+        let mut text = format!("static CHAR_CLASSES: [(char, char, u32); {}] = [\n", ranges.len());
+        for (first, last, class) in &ranges {
+            text += &format!("\t({:?}, {:?}, {}),\n", first, last, class);
+        }
+        text += "];\n\n";
+        text += stringify!(
+            fn char_class(c: char) -> usize
+            {
+                let index = CHAR_CLASSES.binary_search_by(|(first, last, _)| {
+                    if *last < c {
+                        std::cmp::Ordering::Less
+                    } else if *first > c {
+                        std::cmp::Ordering::Greater
+                    } else {
+                        std::cmp::Ordering::Equal
+                    }
+                });
+                match index {
+                    Ok(index) => CHAR_CLASSES[index].2 as usize,
+                    Err(_) => 0,
+                }
+            }
+
+            fn transition(curr: i32, trans: TransitionKind) -> Option<i32>
+            {
+                let class = match trans {
+                    TransitionKind::Character(c) => char_class(c),
+                    TransitionKind::AnyChar => 0,
+                };
+                match TRANSITIONS[curr as usize * CLASS_COUNT + class] {
+                    0 => None,
+                    next => Some(next as i32),
+                }
+            }
+        );
+        text += "\n";
+        text += &CodeGen::create_transition_array(&states, &classes);
+        return text;
+    }
+
+    fn create_byte_table_transition_function(&self) -> String
+    {
+        let dfa = self.table.to_byte_dfa();
+        let mut states: Vec<i32> = dfa.transitions.keys().cloned().collect();
+        states.sort();
+
+        // Bytes with the same destination in every state share a class
+        let mut classes: Vec<Vec<Option<i32>>> = Vec::new();
+        let mut byte_classes: Vec<String> = Vec::new();
+        for byte in 0..=255u8 {
+            let row: Vec<Option<i32>> = states.iter().map(|state| dfa.transitions[state].get(&byte).cloned()).collect();
+            let class = match classes.iter().position(|class| *class == row) {
+                Some(value) => value,
+                None => {
+                    classes.push(row);
+                    classes.len() - 1
+                },
+            };
+            byte_classes.push(class.to_string());
+        }
+
+        let mut text = format!("static BYTE_CLASSES: [u8; 256] = [\n{}];\n\n", CodeGen::wrap_values(&byte_classes));
+        text += stringify!(
+            fn transition(curr: i32, byte: u8) -> Option<i32>
+            {
+                match TRANSITIONS[curr as usize * CLASS_COUNT + BYTE_CLASSES[byte as usize] as usize] {
+                    0 => None,
+                    next => Some(next as i32),
+                }
+            }
+        );
+        text += "\n";
+        text += &CodeGen::create_transition_array(&states, &classes);
+        return text;
+    }
+
+    // Emits the next state for each state and class, where 0 means that there is no transition.
+    // Each class has the destinations of the states in order.
+    fn create_transition_array(states: &Vec<i32>, classes: &Vec<Vec<Option<i32>>>) -> String
+    {
+        let max_state = states.iter().cloned().chain(classes.iter().flatten().flatten().cloned()).max().unwrap_or(1) as usize;
+        let state_type = if max_state <= u16::MAX as usize { "u16" } else { "u32" };
+
+        let mut values = vec!["0".to_string(); (max_state + 1) * classes.len()];
+        for (class, destinations) in classes.iter().enumerate() {
+            for (state, destination) in states.iter().zip(destinations) {
+                if let Some(destination) = destination {
+                    values[*state as usize * classes.len() + class] = destination.to_string();
+                }
+            }
+        }
+
+        let mut text = format!("const CLASS_COUNT: usize = {};\n\n", classes.len());
+        text += &format!("static TRANSITIONS: [{}; {}] = [\n{}];\n", state_type, values.len(), CodeGen::wrap_values(&values));
+        return text;
+    }
+
+    // Writes the values of an array a row at a time
+    fn wrap_values(values: &Vec<String>) -> String
+    {
+        let mut text = String::new();
+        for row in values.chunks(32) {
+            text += &format!("\t{},\n", row.join(", "));
+        }
+        return text;
+    }
+
+    fn create_byte_transition_function(&self) -> String
+    {
+        let dfa = self.table.to_byte_dfa();
+        let mut states: Vec<&i32> = dfa.transitions.keys().collect();
+        states.sort();
+
+        let mut func = "fn transition(curr: i32, byte: u8) -> Option<i32>\n{\n".to_string();
+        func += "\treturn match (curr, byte) {\n";
+        for state in states {
+            // Consecutive bytes with the same destination share a range
+            let mut ranges: Vec<(u8, u8, i32)> = Vec::new();
+            for (byte, dest) in &dfa.transitions[state] {
+                match ranges.last_mut() {
+                    Some(last) if last.2 == *dest && last.1 as u16 + 1 == *byte as u16 => last.1 = *byte,
+                    _ => ranges.push((*byte, *byte, *dest)),
+                }
+            }
+            for (first, last, dest) in ranges {
+                if first == last {
+                    func += &format!("\t\t({}, {}) => Some({}),\n", state, first, dest);
+                } else {
+                    func += &format!("\t\t({}, {}..={}) => Some({}),\n", state, first, last, dest);
+                }
+            }
+        }
+        func += "\t\t_ => None,\n";
+        func += "\t};\n";
+        func += "}\n";
+        return func;
+    }
+
+    pub fn create_transition_kind(&self) -> String 
     {
         stringify!(
             pub enum TransitionKind {
@@ -160,13 +670,18 @@ impl CodeGen {
         ).to_string()
     }
 
-    pub fn create_check_accepting_state_function(&mut self) -> String
+    pub fn create_check_accepting_state_function(&self) -> String
     {
-        let mut header: String = "fn is_accepting(state: i32) -> Option<String>\n{\n".to_string();
+        let variants = self.terminal_variants();
+        // The terminal of the accepting state is None for unnamed rules
+        let mut header: String = "fn is_accepting(state: i32) -> Option<Option<Terminal>>\n{\n".to_string();
         let mut match_statement: String = "\treturn match state {\n".to_string();
         for state in &self.table.accepting_states
         {
-            match_statement += &format!("\t\t{0} => Some(\"{1}\".to_string()),\n", state.0, state.1);
+            match variants.get(state.1) {
+                Some(variant) => match_statement += &format!("\t\t{0} => Some(Some(Terminal::{1})),\n", state.0, variant),
+                None => match_statement += &format!("\t\t{0} => Some(None),\n", state.0),
+            }
         }
         match_statement += "\t\t_ => None\n";
         match_statement += "\t}\n";
@@ -175,7 +690,7 @@ impl CodeGen {
         return header;
     }
 
-    pub fn create_transition_function(&mut self) -> String
+    pub fn create_transition_function(&self) -> String
     {
         let mut header: String = "fn transition(curr: i32, trans: TransitionKind) -> Option<i32>\n{\n".to_string();
 
@@ -194,7 +709,7 @@ impl CodeGen {
                         char_transition_statement = Some("\t\tif let TransitionKind::Character(trans_char) = trans\n\t\t{\n".to_string());
                     }
 
-                    let inner_statement = format!("\t\t\tif trans_char == '{0}'\n \
+                    let inner_statement = format!("\t\t\tif trans_char == {0:?}\n \
                     \t\t\t{{\n \
                         \t\t\t\treturn Some({1});\n\
                     \t\t\t}}\n", character, dest);
@@ -203,10 +718,8 @@ impl CodeGen {
                 }
                 else 
                 {
-                    any_transition_statement = Some(format!("if let TransitionKind::AnyCharacter = trans\n \
-                    \t\t\t{{ \n \
-                        \t\t\t\treturn Some({0});\n\
-                    \t\t\t}}\n", dest));
+                    // Any character without its own transition
+                    any_transition_statement = Some(format!("\t\treturn Some({0});\n", dest));
                 }
             }
 
@@ -229,161 +742,785 @@ impl CodeGen {
         return header;
     }
 
-    fn create_grammar_parse_function(&self) -> String
+    fn create_lexer_action_function(&self) -> String
     {
-        stringify!(
-            pub fn parse(symbols: &Vec<Token>) -> Result<TreeNode, ErrorKind> {
-                
-                let action_table = get_action_table();
-                let goto_table: HashMap<(usize, Symbol), usize> = get_goto_table();
-
-                let mut stack: Vec<StackSymbol> = Vec::new();
-                stack.push(StackSymbol::DollarSign);
-                stack.push(StackSymbol::State(0));
-        
-                let mut word = symbols[0].symbol.clone();
-                let mut word_index = 0;
-
-                let mut node_children: Vec<TreeNode> = vec![];
-
-                let mut start_col = 0;
-                let mut end_col = 0;
-        
-                loop {
-                    let state = match &stack[stack.len() - 1] {
-                        StackSymbol::State(value) => *value,
-                        StackSymbol::Symbol(_) => panic!(),
-                        StackSymbol::DollarSign => panic!(),
-                    };
-
-                    // Print state
-                    //println!("Word: {:?} State: {:?}", word, state);
-                    
-                    let key = (state, word.clone());
-                    
-                    if let Some(action) = action_table.get(&key).clone() {
-
-                        // Print action 
-                        //println!("Action: {:?}", action);
-                        
-                        match action {
-                            Action::Reduce(lhs, prod_len) => {
-                                let num = 2 * prod_len;
-                                for i in 0..num {
-                                    stack.pop();
-                                }
-                                let state = match &stack[stack.len() - 1] {
-                                    StackSymbol::State(value) => *value,
-                                    StackSymbol::Symbol(_) => panic!(),
-                                    StackSymbol::DollarSign => panic!(),
-                                };
-                                stack.push(StackSymbol::Symbol(lhs.clone()));
-                                let goto = match goto_table.get(&(state, lhs.clone())) {
-                                    Some(value) => value,
-                                    None => panic!(),
-                                };
-                                stack.push(StackSymbol::State(*goto));
-
-                                let token = Token::new(lhs.name.to_string(), 0, 0, lhs.clone());
-
-                                let mut new_node_children = Vec::new();
-                                for i in 0..*prod_len {
-                                    let x = node_children.pop().unwrap();
-                                    new_node_children.push(x);
-                                }
-                                let node = TreeNode {token: token, children: new_node_children};
-                                //println!("Reduced: {:#?}", node);
-                                node_children.push(node);
-                                
-                            },
-                            Action::Shift(dest) => {
-                                stack.push(StackSymbol::Symbol(word.clone()));
-                                stack.push(StackSymbol::State(*dest));
-                                
-                                let token = Token::new(symbols[word_index].lexeme.to_string(), symbols[word_index].start_col, symbols[word_index].end_col, word.clone());
-                                let node = TreeNode {token: token, children: Vec::new()};
-                                node_children.push(node);
-
-                                word_index += 1;
-                                word = symbols[word_index].symbol.clone();
-                                //println!("Shift");
-                                //println!("{:?}", node_children);
-                            },
-                            Action::Accept => break,
-                        }
-        
-                    }
-                    else {
-                        return Err(ErrorKind::GrammarParseFailed);
-                    }
-                }
-                
-                let root_node = TreeNode {token: Token::new("root".to_string(), 0, 0, Symbol {name: "root".to_string(), is_terminal: false}), children: node_children};
-                return Ok(root_node);
+        // Group the accepting states by the rule they accept
+        let mut states_by_rule: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+        for (state, priority) in &self.table.accepting_rules {
+            if self.lexer_actions.contains_key(priority) {
+                states_by_rule.entry(*priority).or_insert(Vec::new()).push(*state);
             }
-        ).to_string()
-    }
+        }
 
-    fn create_get_action_table_function(&self) -> String
-    {
-        let mut func = "fn get_action_table() -> HashMap<(usize, Symbol), Action> {\n".to_string();
-        func += "return ";
-        func += &self.create_action_table();
+        let mut func = "#[allow(unused_variables)]\nfn run_lexer_action(state: i32, lexeme: &str)\n{\n".to_string();
+        func += "\tmatch state {\n";
+        for (priority, states) in &mut states_by_rule {
+            states.sort();
+            let states: Vec<String> = states.iter().map(|state| state.to_string()).collect();
+            func += &format!("\t\t{} => {}\n", states.join(" | "), self.lexer_actions[priority]);
+        }
+        func += "\t\t_ => (),\n";
+        func += "\t}\n";
         func += "}\n";
         return func;
     }
 
-    fn create_get_goto_table_function(&self) -> String
+    fn create_reduce_action_function(&self) -> String
     {
-        let mut func = "fn get_goto_table() -> HashMap<(usize, Symbol), usize> {\n".to_string();
-        func += "return ";
-        func += &self.create_goto_table();
+        let mut func = "#[allow(unused_variables)]\nfn run_reduce_action(production: usize, arguments: &Vec<TreeNode>)\n{\n".to_string();
+        // Returns the tree node of the x-th symbol of the production
+        func += "\tlet get_argument = |x: usize| &arguments[x];\n";
+        func += "\tmatch production {\n";
+        for prod in &self.grammar_gen.productions {
+            unsafe {
+                // In typed mode the actions run in reduce_value instead
+                if let (Some(action), false) = (&(**prod).action, self.is_typed()) {
+                    func += &format!("\t\t{} => {}\n", (**prod).id, action);
+                }
+            }
+        }
+        func += "\t\t_ => (),\n";
+        func += "\t}\n";
         func += "}\n";
         return func;
     }
 
-    fn create_action_table(&self) -> String
+    // The productions of x* and x+ that add an x to the list, whose node is flattened into the new node
+    fn create_extends_list_function(&self) -> String
     {
-        let mut table = "HashMap::from([\n".to_string();
-        for (key, value) in &self.grammar_gen.action_table {
-            let action_str = match value {
-                crate::grammar2::Action::Accept => "Action::Accept".to_string(),
-                crate::grammar2::Action::Shift(state) => format!("Action::Shift({})", state),
-                crate::grammar2::Action::Reduce(symbol, size) => format!("Action::Reduce(Symbol {{name: \"{}\".to_string(), is_terminal: {}}}, {})", symbol.name, symbol.is_terminal, size),
-            };
-            table += &format!("\t(({}, Symbol {{name: \"{}\".to_string(), is_terminal: {} }}), {}),\n", key.0, key.1.name, key.1.is_terminal, action_str);
+        let mut productions = Vec::new();
+        for rule in self.rules_in_order() {
+            if rule.helper != Some(HelperKind::Repetition) {
+                continue;
+            }
+            for prod in &rule.productions {
+                let prod: &Production = unsafe { &**prod };
+                if prod.prod.first().map(|symbol| &symbol.name) == Some(&rule.name) {
+                    productions.push(prod.id.to_string());
+                }
+            }
         }
-        table += "]);\n";
-        return table;
-    }
 
-    fn create_goto_table(&self) -> String
-    {
-        let mut table = "HashMap::from([\n".to_string();
-        for (key, value) in &self.grammar_gen.goto_table {
-            table += &format!("(({}, Symbol {{name: \"{}\".to_string(), is_terminal: {} }}), {}),\n", key.0, key.1.name, key.1.is_terminal, value);
+        let mut func = "#[allow(unused_variables)]\nfn extends_list(production: usize) -> bool\n{\n".to_string();
+        match productions.is_empty() {
+            true => func += "\tfalse\n",
+            false => func += &format!("\tmatches!(production, {})\n", productions.join(" | ")),
         }
-        table += "]);\n";
-        return table;
+        func += "}\n";
+        return func;
     }
 
-    // Structs & enums
-    fn create_structs_and_enums(&self) -> String
+    fn create_grammar_parse_function(&self) -> String
     {
-        let mut text = self.create_symbol_struct();
-        text += "\n";
-        text += &self.create_token_struct();
-        text += "\n";
-        text += &self.create_action_struct();
-        text += "\n";
-        text += &self.create_stack_symbol_struct();
-        text += "\n";
-        text += &self.create_tree_node_struct();
-        text += "\n";
-        text += &self.create_error_enum();
-        text += "\n";
-        return text;
-    }
+        stringify!(
+            // Runs the LR parser and builds a value of type V for each symbol using the given functions.
+            // Syntax errors are recorded in errors and recovered from with the productions that use the error token.
+            fn run_parser<V>(mut tokens: impl Iterator<Item = Result<Token, ErrorKind>>, errors: &mut Vec<SyntaxError>, mut shift: impl FnMut(&Token) -> V, mut reduce: impl FnMut(NonTerminal, usize, Vec<V>, &Token) -> V) -> Result<V, ErrorKind> {
+
+                // The states of the parser, where the symbol of each state is the symbol of its value
+                let mut stack: Vec<usize> = vec![0];
+
+                // Values of the symbols on the stack
+                let mut values: Vec<V> = vec![];
+
+                // The tokens are read one at a time as the parser needs them
+                let mut lookahead = next_token(&mut tokens, &Token::new(String::new(), Symbol::Terminal(Terminal::Eof), 1, 1, 0))?;
+                let mut terminal = terminal_id(lookahead.symbol);
+
+                // Number of tokens left to shift before new syntax errors are reported
+                let mut recovering = 0;
+
+                // The state that could not accept the lookahead, before the reductions made for the error token
+                let mut error_state: Option<usize> = None;
+
+                loop {
+                    let state = stack[stack.len() - 1];
+                    let mut action = get_action(state, terminal);
+
+                    // Reductions that can come before an error token are made before recovering so that complete phrases are kept
+                    if action.is_none() && recovering < 3 {
+                        if let Some(Action::Reduce(production)) = get_action(state, Some(Terminal::Error as usize)) {
+                            error_state.get_or_insert(state);
+                            action = Some(Action::Reduce(production));
+                        }
+                    }
+
+                    match action {
+                        Some(Action::Reduce(production)) => {
+                            let (lhs, length) = PRODUCTIONS[production];
+                            stack.truncate(stack.len() - length);
+                            let state = stack[stack.len() - 1];
+                            stack.push(get_goto(state, lhs));
+
+                            // Children are kept in the order they appear in the production
+                            let children = values.split_off(values.len() - length);
+                            values.push(reduce(lhs, production, children, &lookahead));
+                        },
+                        Some(Action::Shift(dest)) => {
+                            stack.push(dest);
+                            values.push(shift(&lookahead));
+                            error_state = None;
+
+                            lookahead = next_token(&mut tokens, &lookahead)?;
+                            terminal = terminal_id(lookahead.symbol);
+                            if recovering > 0 {
+                                recovering -= 1;
+                            }
+                        },
+                        Some(Action::Accept) => {
+                            // The value of root is the only value left
+                            return Ok(values.pop().unwrap());
+                        },
+                        None if recovering < 3 => {
+                            // Errors are not reported again until three tokens have been shifted
+                            // The expected terminals are those of the state that first rejected the lookahead
+                            let error = SyntaxError::new(error_state.take().unwrap_or(state), lookahead.clone());
+                            if recovering == 0 {
+                                errors.push(error.clone());
+                            }
+                            recovering = 3;
+
+                            // Pop states until one of them can shift the error token
+                            let dest = loop {
+                                if let Some(Action::Shift(dest)) = get_action(stack[stack.len() - 1], Some(Terminal::Error as usize)) {
+                                    break dest;
+                                }
+                                if stack.len() <= 1 {
+                                    return Err(ErrorKind::GrammarParseFailed(error));
+                                }
+                                stack.pop();
+                                values.pop();
+                            };
+
+                            // The error token is empty at the start of the token that could not be parsed
+                            let error_token = Token::new(String::new(), Symbol::Terminal(Terminal::Error), lookahead.line, lookahead.start_col, lookahead.start_offset);
+                            stack.push(dest);
+                            values.push(shift(&error_token));
+                        },
+                        None => {
+                            // Discard tokens until one of them can follow the error token
+                            if lookahead.symbol == Symbol::Terminal(Terminal::Eof) {
+                                return Err(ErrorKind::GrammarParseFailed(SyntaxError::new(state, lookahead.clone())));
+                            }
+                            lookahead = next_token(&mut tokens, &lookahead)?;
+                            terminal = terminal_id(lookahead.symbol);
+                        },
+                    }
+                }
+            }
+
+            // Returns the next token, or an eof token after the previous one if there are no tokens left
+            fn next_token(tokens: &mut impl Iterator<Item = Result<Token, ErrorKind>>, previous: &Token) -> Result<Token, ErrorKind> {
+                match tokens.next() {
+                    Some(token) => token,
+                    None => Ok(Token::new(String::new(), Symbol::Terminal(Terminal::Eof), previous.end_line, previous.end_col, previous.end_offset)),
+                }
+            }
+
+            // Parses the tokens as they are read, e.g. from a Lexer, and records every syntax error that was recovered from.
+            // Fails if the parser could not recover or a token could not be read.
+            pub fn parse_stream(tokens: impl Iterator<Item = Result<Token, ErrorKind>>, errors: &mut Vec<SyntaxError>) -> Result<TreeNode, ErrorKind> {
+                run_parser(tokens, errors, |token| TreeNode {token: token.clone(), children: Vec::new()}, |lhs, production, mut children, lookahead| {
+                    run_reduce_action(production, &children);
+                    // The items of x* and x+ are children of one node instead of nested nodes
+                    if extends_list(production) {
+                        let mut items = children.remove(0).children;
+                        items.append(&mut children);
+                        children = items;
+                    }
+                    TreeNode::from_children(lhs, children, lookahead)
+                })
+            }
+
+            // Parses the tokens and returns the tree along with every syntax error that was recovered from.
+            // The tree is None if the parser could not recover.
+            pub fn parse_with_errors(symbols: &Vec<Token>) -> (Option<TreeNode>, Vec<SyntaxError>) {
+                let mut errors = Vec::new();
+                let tree = parse_stream(symbols.iter().cloned().map(Ok), &mut errors);
+                (tree.ok(), errors)
+            }
+
+            pub fn parse(symbols: &Vec<Token>) -> Result<TreeNode, ErrorKind> {
+                match parse_with_errors(symbols) {
+                    (Some(tree), errors) if errors.is_empty() => Ok(tree),
+                    // The parser only stops after a syntax error, so the first error is always there
+                    (_, errors) => Err(ErrorKind::GrammarParseFailed(errors[0].clone())),
+                }
+            }
+        ).to_string()
+    }
+
+    fn is_typed(&self) -> bool
+    {
+        return !self.value_types.is_empty();
+    }
+
+    // Converts a rule name such as primary_expr into a type name such as PrimaryExpr
+    fn to_type_name(name: &str) -> String
+    {
+        let mut type_name = String::new();
+        let mut capitalize = true;
+        for c in name.chars() {
+            if c == '_' || c == '-' {
+                capitalize = true;
+            } else if capitalize {
+                type_name.extend(c.to_uppercase());
+                capitalize = false;
+            } else {
+                type_name.push(c);
+            }
+        }
+        return type_name;
+    }
+
+    // Replaces $$ with the result variable and $n with the n-th argument variable.
+    // String and char literals are copied as they are.
+    pub(crate) fn replace_value_references(code: &str) -> String
+    {
+        let chars: Vec<char> = code.chars().collect();
+        let mut result = String::new();
+        let mut index = 0;
+        while index < chars.len() {
+            if let Some(end) = CodeGen::find_literal_end(&chars, index) {
+                result.extend(&chars[index..end]);
+                index = end;
+                continue;
+            }
+            if chars[index] == '$' && index + 1 < chars.len() {
+                if chars[index + 1] == '$' {
+                    result.push_str("__result");
+                    index += 2;
+                    continue;
+                }
+
+                let mut number = String::new();
+                let mut end = index + 1;
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    number.push(chars[end]);
+                    end += 1;
+                }
+                if !number.is_empty() {
+                    result.push_str(&format!("__arg{}", number));
+                    index = end;
+                    continue;
+                }
+            }
+            result.push(chars[index]);
+            index += 1;
+        }
+        return result;
+    }
+
+    // Returns the index after the string or char literal that starts at the index, if there is one.
+    // A quote that is not followed by a char and a quote starts a lifetime such as 'static.
+    fn find_literal_end(chars: &Vec<char>, index: usize) -> Option<usize>
+    {
+        let quote = chars[index];
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        if quote == '\'' && chars.get(index + 1) != Some(&'\\') && chars.get(index + 2) != Some(&'\'') {
+            return None;
+        }
+
+        let mut end = index + 1;
+        while end < chars.len() && chars[end] != quote {
+            // Skip the escaped character
+            if chars[end] == '\\' {
+                end += 1;
+            }
+            end += 1;
+        }
+        return Some(usize::min(end + 1, chars.len()));
+    }
+
+    fn create_semantic_value_enum(&self) -> String
+    {
+        let mut text = "#[derive(Debug)]\npub enum SemanticValue {\n".to_string();
+        text += "\tToken(Token),\n";
+        text += "\tUntyped,\n";
+
+        let variants = self.nonterminal_variants();
+        for name in self.nonterminal_names() {
+            if let Some(value_type) = self.semantic_type(&name) {
+                text += &format!("\t{}({}),\n", variants[&name], value_type);
+            }
+        }
+        text += "}\n";
+        return text;
+    }
+
+    // The type of the value of a rule, or None if its value is (). The rules of x? and x* or x+ have
+    // an Option or a Vec of the value of x, and a group with one alternative has a tuple of its values.
+    fn semantic_type(&self, name: &str) -> Option<String>
+    {
+        if let Some(value_type) = self.value_types.get(name) {
+            return Some(value_type.to_string());
+        }
+
+        let rule = self.get_rule_by_name(name);
+        let productions: Vec<&Production> = rule.productions.iter().map(|prod| unsafe { &**prod }).collect();
+        // The item of x?, x* and x+ is the last symbol of the last production
+        let item = || self.symbol_value_type(productions.last().unwrap().prod.last().unwrap());
+        return match rule.helper {
+            Some(HelperKind::Optional) => item().map(|value_type| format!("std::option::Option<{}>", value_type)),
+            Some(HelperKind::Repetition) => item().map(|value_type| format!("std::vec::Vec<{}>", value_type)),
+            Some(HelperKind::Group) if productions.len() == 1 => {
+                let types: Vec<String> = productions[0].prod.iter().map(|symbol| self.symbol_value_type(symbol).unwrap_or("()".to_string())).collect();
+                match types.len() {
+                    0 => None,
+                    _ => Some(format!("({},)", types.join(", "))),
+                }
+            },
+            _ => None,
+        };
+    }
+
+    fn symbol_value_type(&self, symbol: &crate::grammar2::Symbol) -> Option<String>
+    {
+        if symbol.is_terminal {
+            return Some("Token".to_string());
+        }
+        return self.semantic_type(&symbol.name);
+    }
+
+    // Returns the expression that converts a SemanticValue into the value of the symbol
+    fn unwrap_semantic_value(&self, symbol: &crate::grammar2::Symbol, value: &str) -> String
+    {
+        if symbol.is_terminal {
+            return format!("match {} {{ SemanticValue::Token(value) => value, _ => unreachable!() }}", value);
+        }
+        if self.semantic_type(&symbol.name).is_some() {
+            return format!("match {} {{ SemanticValue::{}(value) => value, _ => unreachable!() }}", value, self.nonterminal_variants()[&symbol.name]);
+        }
+        return format!("match {} {{ SemanticValue::Untyped => (), _ => unreachable!() }}", value);
+    }
+
+    fn create_reduce_value_function(&self) -> String
+    {
+        let mut func = "#[allow(unused_variables, unused_mut, unreachable_code)]\nfn reduce_value(production: usize, arguments: Vec<SemanticValue>) -> SemanticValue\n{\n".to_string();
+        func += "\tlet mut arguments = arguments.into_iter();\n";
+        func += "\tmatch production {\n";
+        let variants = self.nonterminal_variants();
+        for rule in self.rules_in_order() {
+            let value_type = self.semantic_type(&rule.name);
+            for prod in &rule.productions {
+                unsafe {
+                    let prod: &Production = &**prod;
+                    func += &format!("\t\t{} => {{\n", prod.id);
+
+                    // Take the values of the symbols in the production
+                    for (i, symbol) in prod.prod.iter().enumerate() {
+                        func += &format!("\t\t\tlet mut __arg{} = {};\n", i + 1, self.unwrap_semantic_value(symbol, "arguments.next().unwrap()"));
+                    }
+
+                    let value_type = match &value_type {
+                        Some(value) => value,
+                        None => {
+                            if let Some(action) = &prod.action {
+                                func += &format!("\t\t\t{}\n", CodeGen::replace_value_references(action));
+                            }
+                            func += "\t\t\tSemanticValue::Untyped\n";
+                            func += "\t\t},\n";
+                            continue;
+                        }
+                    };
+
+                    func += &format!("\t\t\tlet mut __result: {};\n", value_type);
+                    let arguments: Vec<String> = (1..=prod.prod.len()).map(|i| format!("__arg{}", i)).collect();
+                    if let Some(action) = &prod.action {
+                        func += &format!("\t\t\t{}\n", CodeGen::replace_value_references(action));
+                    } else if let Some(kind) = rule.helper {
+                        // x? is None or Some of x, x* and x+ add x to the list, and a group is a tuple of its values
+                        func += &match (kind, prod.prod.len()) {
+                            (HelperKind::Optional, 0) => "\t\t\t__result = None;\n".to_string(),
+                            (HelperKind::Optional, _) => "\t\t\t__result = Some(__arg1);\n".to_string(),
+                            (HelperKind::Repetition, 0) => "\t\t\t__result = std::vec::Vec::new();\n".to_string(),
+                            (HelperKind::Repetition, 1) => "\t\t\t__result = vec![__arg1];\n".to_string(),
+                            (HelperKind::Repetition, _) => "\t\t\t__arg1.push(__arg2);\n\t\t\t__result = __arg1;\n".to_string(),
+                            (HelperKind::Group, _) => format!("\t\t\t__result = ({},);\n", arguments.join(", ")),
+                        };
+                    } else if prod.prod.len() > 0 && !prod.prod[0].is_terminal && self.semantic_type(&prod.prod[0].name).as_ref() == Some(value_type) {
+                        // Default action $$ = $1
+                        func += "\t\t\t__result = __arg1;\n";
+                    } else {
+                        func += "\t\t\t__result = Default::default();\n";
+                    }
+                    func += &format!("\t\t\tSemanticValue::{}(__result)\n", variants[&rule.name]);
+                    func += "\t\t},\n";
+                }
+            }
+        }
+        func += "\t\t_ => unreachable!(),\n";
+        func += "\t}\n";
+        func += "}\n";
+        return func;
+    }
+
+    fn create_parse_value_function(&self) -> String
+    {
+        let root = crate::grammar2::Symbol::new("root".to_string(), false, crate::grammar2::Empty::NonEmpty);
+        let root_type = match self.value_types.get("root") {
+            Some(value) => value.to_string(),
+            None => "()".to_string(),
+        };
+
+        let mut func = format!("pub fn parse_value_stream(tokens: impl Iterator<Item = Result<Token, ErrorKind>>, errors: &mut Vec<SyntaxError>) -> Result<{}, ErrorKind> {{\n", root_type);
+        func += "\tlet value = run_parser(tokens, errors, |token| SemanticValue::Token(token.clone()), |_, production, arguments, _| reduce_value(production, arguments))?;\n";
+        func += &format!("\treturn Ok({});\n", self.unwrap_semantic_value(&root, "value"));
+        func += "}\n\n";
+
+        func += &format!("pub fn parse_value_with_errors(symbols: &Vec<Token>) -> (Option<{}>, Vec<SyntaxError>) {{\n", root_type);
+        func += "\tlet mut errors = Vec::new();\n";
+        func += "\tlet value = parse_value_stream(symbols.iter().cloned().map(Ok), &mut errors);\n";
+        func += "\treturn (value.ok(), errors);\n";
+        func += "}\n\n";
+
+        func += &format!("pub fn parse_value(symbols: &Vec<Token>) -> Result<{}, ErrorKind> {{\n", root_type);
+        func += "\treturn match parse_value_with_errors(symbols) {\n";
+        func += "\t\t(Some(value), errors) if errors.is_empty() => Ok(value),\n";
+        func += "\t\t(_, errors) => Err(ErrorKind::GrammarParseFailed(errors[0].clone())),\n";
+        func += "\t};\n";
+        func += "}\n";
+        return func;
+    }
+
+    // The grammar rules in the order they were declared
+    fn rules_in_order(&self) -> Vec<&GrammarRule>
+    {
+        let mut rules: Vec<&GrammarRule> = self.grammar_gen.non_terminals.iter().filter_map(|nt| self.grammar_gen.get_rule(nt)).collect();
+        rules.sort_by_key(|rule| unsafe { (*rule.productions[0]).id });
+        return rules;
+    }
+
+    // Returns the rule of a nonterminal in a production
+    fn get_rule_by_name(&self, name: &str) -> &GrammarRule
+    {
+        let symbol = crate::grammar2::Symbol::new(name.to_string(), false, crate::grammar2::Empty::NonEmpty);
+        return self.grammar_gen.get_rule(&symbol).unwrap();
+    }
+
+    // The type of a symbol in the syntax tree. x? is an Option and x* and x+ are a Vec, so only the
+    // rules and groups have an enum. In the ast module the types of the parent module need super::.
+    fn ast_type(&self, symbol: &crate::grammar2::Symbol, in_module: bool, boxed: bool) -> String
+    {
+        if symbol.is_terminal {
+            return match in_module {
+                true => "super::Token".to_string(),
+                false => "Token".to_string(),
+            };
+        }
+
+        let rule = self.get_rule_by_name(&symbol.name);
+        // The item of x?, x* and x+ is the last symbol of the last production
+        let item = || unsafe { (**rule.productions.last().unwrap()).prod.last().unwrap() };
+        match rule.helper {
+            Some(HelperKind::Optional) => return format!("std::option::Option<{}>", self.ast_type(item(), in_module, true)),
+            Some(HelperKind::Repetition) => return format!("std::vec::Vec<{}>", self.ast_type(item(), in_module, false)),
+            _ => (),
+        }
+        let rule_type = match in_module {
+            true => self.nonterminal_variants()[&symbol.name].to_string(),
+            false => format!("ast::{}", self.nonterminal_variants()[&symbol.name]),
+        };
+        return match boxed {
+            true => format!("std::boxed::Box<{}>", rule_type),
+            false => rule_type,
+        };
+    }
+
+    // Each rule becomes an enum in the ast module with a variant for each production. The fields of a
+    // variant are the tokens of the terminals and the boxed nodes of the rules in the production.
+    fn create_ast_types(&self) -> String
+    {
+        let rule_types = self.nonterminal_variants();
+        let mut text = "pub mod ast {\n".to_string();
+        for rule in self.rules_in_order() {
+            if matches!(rule.helper, Some(HelperKind::Optional) | Some(HelperKind::Repetition)) {
+                continue;
+            }
+            text += "\t#[derive(Debug, Clone, PartialEq)]\n";
+            text += &format!("\tpub enum {} {{\n", rule_types[&rule.name]);
+            for (variant, prod) in self.production_variants(rule) {
+                // The types of the parent module are not imported, so rules can have the same names
+                let fields: Vec<String> = prod.prod.iter().map(|symbol| self.ast_type(symbol, true, true)).collect();
+                match fields.is_empty() {
+                    true => text += &format!("\t\t{},\n", variant),
+                    false => text += &format!("\t\t{}({}),\n", variant, fields.join(", ")),
+                }
+            }
+            text += "\t}\n\n";
+        }
+        text += "}\n\n";
+
+        // The values on the stack of the parser while it builds the tree
+        text += "#[derive(Debug)]\nenum AstNode {\n\tToken(Token),\n\tRule(AstRule),\n}\n\n";
+        text += "#[derive(Debug)]\nenum AstRule {\n";
+        for rule in self.rules_in_order() {
+            let symbol = crate::grammar2::Symbol::new(rule.name.to_string(), false, crate::grammar2::Empty::NonEmpty);
+            text += &format!("\t{}({}),\n", rule_types[&rule.name], self.ast_type(&symbol, false, false));
+        }
+        text += "}\n";
+        return text;
+    }
+
+    // Names the variant of each production after its symbols, e.g. ExprPlusTerm for expr "+" term
+    fn production_variants<'a>(&self, rule: &'a GrammarRule) -> Vec<(String, &'a Production)>
+    {
+        let terminal_variants = self.terminal_variants();
+        let rule_types = self.nonterminal_variants();
+        let mut taken: BTreeSet<String> = BTreeSet::new();
+        taken.insert("Self".to_string());
+
+        let mut variants = Vec::new();
+        for prod in &rule.productions {
+            let prod: &Production = unsafe { &**prod };
+            let mut variant: String = prod.prod.iter().map(|symbol| match symbol.is_terminal {
+                true => terminal_variants[&symbol.name].as_str(),
+                false => rule_types[&symbol.name].as_str(),
+            }).collect();
+            if variant.is_empty() {
+                variant = "Empty".to_string();
+            }
+            variants.push((CodeGen::take_unique_name(variant, &mut taken), prod));
+        }
+        return variants;
+    }
+
+    fn create_reduce_ast_function(&self) -> String
+    {
+        let rule_types = self.nonterminal_variants();
+        let mut func = "#[allow(unused_mut)]\nfn reduce_ast(production: usize, arguments: Vec<AstNode>) -> AstNode\n{\n".to_string();
+        func += "\tlet mut arguments = arguments.into_iter();\n";
+        func += "\tlet node = match production {\n";
+        for rule in self.rules_in_order() {
+            let rule_type = &rule_types[&rule.name];
+            for (variant, prod) in self.production_variants(rule) {
+                func += &format!("\t\t{} => {{\n", prod.id);
+
+                // Take the nodes of the symbols in the production. The items of x* and x+ are not boxed.
+                let mut fields = Vec::new();
+                for (i, symbol) in prod.prod.iter().enumerate() {
+                    let value = match symbol.is_terminal {
+                        true => "match arguments.next() { Some(AstNode::Token(token)) => token, _ => unreachable!() }".to_string(),
+                        false => match (self.get_rule_by_name(&symbol.name).helper, rule.helper) {
+                            (Some(HelperKind::Optional), _) | (Some(HelperKind::Repetition), _) | (_, Some(HelperKind::Repetition)) => format!("match arguments.next() {{ Some(AstNode::Rule(AstRule::{}(node))) => node, _ => unreachable!() }}", rule_types[&symbol.name]),
+                            _ => format!("match arguments.next() {{ Some(AstNode::Rule(AstRule::{}(node))) => std::boxed::Box::new(node), _ => unreachable!() }}", rule_types[&symbol.name]),
+                        },
+                    };
+                    func += &format!("\t\t\tlet mut __arg{} = {};\n", i + 1, value);
+                    fields.push(format!("__arg{}", i + 1));
+                }
+                func += &match (rule.helper, fields.len()) {
+                    // x? is None or Some of the item, and x* and x+ add the item to the list
+                    (Some(HelperKind::Optional), 0) => format!("\t\t\tAstRule::{}(None)\n", rule_type),
+                    (Some(HelperKind::Optional), _) => format!("\t\t\tAstRule::{}(Some(__arg1))\n", rule_type),
+                    (Some(HelperKind::Repetition), 0) => format!("\t\t\tAstRule::{}(std::vec::Vec::new())\n", rule_type),
+                    (Some(HelperKind::Repetition), 1) => format!("\t\t\tAstRule::{}(vec![__arg1])\n", rule_type),
+                    (Some(HelperKind::Repetition), _) => format!("\t\t\t__arg1.push(__arg2);\n\t\t\tAstRule::{}(__arg1)\n", rule_type),
+                    (_, 0) => format!("\t\t\tAstRule::{0}(ast::{0}::{1})\n", rule_type, variant),
+                    (_, _) => format!("\t\t\tAstRule::{0}(ast::{0}::{1}({2}))\n", rule_type, variant, fields.join(", ")),
+                };
+                func += "\t\t},\n";
+            }
+        }
+        func += "\t\t_ => unreachable!(),\n";
+        func += "\t};\n";
+        func += "\treturn AstNode::Rule(node);\n";
+        func += "}\n";
+        return func;
+    }
+
+    fn create_parse_ast_function(&self) -> String
+    {
+        let root_type = &self.nonterminal_variants()["root"];
+
+        let mut func = format!("pub fn parse_ast_stream(tokens: impl Iterator<Item = Result<Token, ErrorKind>>, errors: &mut Vec<SyntaxError>) -> Result<ast::{}, ErrorKind> {{\n", root_type);
+        func += "\tlet node = run_parser(tokens, errors, |token| AstNode::Token(token.clone()), |_, production, arguments, _| reduce_ast(production, arguments))?;\n";
+        func += "\treturn match node {\n";
+        func += &format!("\t\tAstNode::Rule(AstRule::{}(root)) => Ok(root),\n", root_type);
+        func += "\t\t_ => unreachable!(),\n";
+        func += "\t};\n";
+        func += "}\n\n";
+
+        func += &format!("pub fn parse_ast_with_errors(symbols: &Vec<Token>) -> (Option<ast::{}>, Vec<SyntaxError>) {{\n", root_type);
+        func += "\tlet mut errors = Vec::new();\n";
+        func += "\tlet root = parse_ast_stream(symbols.iter().cloned().map(Ok), &mut errors);\n";
+        func += "\treturn (root.ok(), errors);\n";
+        func += "}\n\n";
+
+        func += &format!("pub fn parse_ast(symbols: &Vec<Token>) -> Result<ast::{}, ErrorKind> {{\n", root_type);
+        func += "\treturn match parse_ast_with_errors(symbols) {\n";
+        func += "\t\t(Some(root), errors) if errors.is_empty() => Ok(root),\n";
+        func += "\t\t(_, errors) => Err(ErrorKind::GrammarParseFailed(errors[0].clone())),\n";
+        func += "\t};\n";
+        func += "}\n";
+        return func;
+    }
+
+    // The terminals and rules are numbered in the order of their names. Each state has a row
+    // of actions for the terminals and a row of gotos for the rules, and states with the same
+    // row share it. An action is 0 for none, 1 for accept, 2 + 2 * state for a shift and
+    // 3 + 2 * production for a reduce.
+    fn create_parse_tables(&self) -> String
+    {
+        let terminals = self.terminal_names();
+        let nonterminals = self.nonterminal_names();
+        let nonterminal_variants = self.nonterminal_variants();
+
+        let mut productions: Vec<(usize, &String, usize)> = Vec::new();
+        for symbol in &self.grammar_gen.non_terminals {
+            if let Some(rule) = self.grammar_gen.get_rule(symbol) {
+                let lhs = &nonterminal_variants[&rule.name];
+                for prod in &rule.productions {
+                    unsafe {
+                        productions.push(((**prod).id, lhs, (**prod).prod.len()));
+                    }
+                }
+            }
+        }
+        productions.sort();
+
+        let state_count = self.grammar_gen.action_table.keys().map(|(state, _)| *state)
+            .chain(self.grammar_gen.goto_table.keys().map(|(state, _)| *state))
+            .max().unwrap_or(0) + 1;
+
+        let mut action_rows = vec![vec![0usize; terminals.len()]; state_count];
+        for ((state, symbol), action) in &self.grammar_gen.action_table {
+            let terminal = terminals.binary_search(&symbol.name).unwrap();
+            action_rows[*state][terminal] = match action {
+                crate::grammar2::Action::Accept => 1,
+                crate::grammar2::Action::Shift(dest) => 2 + 2 * dest,
+                crate::grammar2::Action::Reduce(_, _, prod_id) => 3 + 2 * prod_id,
+            };
+        }
+        let mut goto_rows = vec![vec![0usize; nonterminals.len()]; state_count];
+        for ((state, symbol), dest) in &self.grammar_gen.goto_table {
+            let nonterminal = nonterminals.binary_search(&symbol.name).unwrap();
+            goto_rows[*state][nonterminal] = *dest;
+        }
+
+        // The rule and the length of each production
+        let production_values: Vec<String> = productions.iter().map(|(_, lhs, len)| format!("(NonTerminal::{}, {})", lhs, len)).collect();
+        let mut text = format!("static PRODUCTIONS: [(NonTerminal, usize); {}] = [\n{}];\n\n", productions.len(), CodeGen::wrap_values(&production_values));
+
+        text += &CodeGen::create_compressed_rows("ACTION", &action_rows);
+        text += &CodeGen::create_compressed_rows("GOTO", &goto_rows);
+        text += stringify!(
+            // Tokens of rules are not in the table
+            fn terminal_id(symbol: Symbol) -> Option<usize>
+            {
+                match symbol {
+                    Symbol::Terminal(terminal) => Some(terminal as usize),
+                    Symbol::NonTerminal(_) => None,
+                }
+            }
+
+            fn get_action(state: usize, terminal: Option<usize>) -> Option<Action>
+            {
+                let action = match terminal {
+                    Some(terminal) => ACTIONS[ACTION_ROWS[state] as usize * TERMINALS.len() + terminal],
+                    None => 0,
+                };
+                match action {
+                    0 => None,
+                    1 => Some(Action::Accept),
+                    value if value % 2 == 0 => Some(Action::Shift((value as usize - 2) / 2)),
+                    value => Some(Action::Reduce((value as usize - 3) / 2)),
+                }
+            }
+
+            fn get_goto(state: usize, nonterminal: NonTerminal) -> usize
+            {
+                GOTOS[GOTO_ROWS[state] as usize * NONTERMINALS.len() + nonterminal as usize] as usize
+            }
+        );
+        text += "\n";
+        return text;
+    }
+
+    // Emits the distinct rows as one array and the index of the row of each state as another
+    fn create_compressed_rows(name: &str, rows: &Vec<Vec<usize>>) -> String
+    {
+        let mut distinct: Vec<&Vec<usize>> = Vec::new();
+        let mut row_ids: Vec<String> = Vec::new();
+        for row in rows {
+            let id = match distinct.iter().position(|other| *other == row) {
+                Some(value) => value,
+                None => {
+                    distinct.push(row);
+                    distinct.len() - 1
+                },
+            };
+            row_ids.push(id.to_string());
+        }
+
+        let values: Vec<String> = distinct.iter().flat_map(|row| row.iter().map(|value| value.to_string())).collect();
+        let max_value = distinct.iter().flat_map(|row| row.iter()).max().cloned().unwrap_or(0);
+        let mut text = format!("static {}_ROWS: [{}; {}] = [\n{}];\n", name, CodeGen::unsigned_type(distinct.len()), row_ids.len(), CodeGen::wrap_values(&row_ids));
+        text += &format!("static {}S: [{}; {}] = [\n{}];\n\n", name, CodeGen::unsigned_type(max_value), values.len(), CodeGen::wrap_values(&values));
+        return text;
+    }
+
+    // The smallest unsigned type that holds the value
+    fn unsigned_type(max_value: usize) -> &'static str
+    {
+        if max_value <= u8::MAX as usize {
+            return "u8";
+        } else if max_value <= u16::MAX as usize {
+            return "u16";
+        }
+        return "u32";
+    }
+
+    // Lists the terminals with an action in each state. States with the same terminals share an arm of the match.
+    fn create_get_expected_terminals_function(&self) -> String
+    {
+        let mut expected: std::collections::BTreeMap<usize, std::collections::BTreeSet<&str>> = std::collections::BTreeMap::new();
+        for (state, symbol) in self.grammar_gen.action_table.keys() {
+            let names = expected.entry(*state).or_default();
+            if symbol.name != crate::grammar2::Symbol::ERROR_NAME {
+                names.insert(&symbol.name);
+            }
+        }
+
+        let mut arms: Vec<(Vec<usize>, &std::collections::BTreeSet<&str>)> = Vec::new();
+        for (state, names) in &expected {
+            match arms.iter_mut().find(|(_, arm_names)| *arm_names == names) {
+                Some((states, _)) => states.push(*state),
+                None => arms.push((vec![*state], names)),
+            }
+        }
+
+        let variants = self.terminal_variants();
+        let mut func = "fn get_expected_terminals(state: usize) -> &'static [Terminal] {\n".to_string();
+        func += "\treturn match state {\n";
+        for (states, names) in arms {
+            let states: Vec<String> = states.iter().map(|state| state.to_string()).collect();
+            let names: Vec<String> = names.iter().map(|name| format!("Terminal::{}", variants[*name])).collect();
+            func += &format!("\t\t{} => &[{}],\n", states.join(" | "), names.join(", "));
+        }
+        func += "\t\t_ => &[],\n";
+        func += "\t};\n";
+        func += "}\n";
+        return func;
+    }
+
+    // Structs & enums
+    fn create_structs_and_enums(&self) -> String
+    {
+        let mut text = self.create_symbol_enums();
+        text += "\n";
+        text += &self.create_token_struct();
+        text += "\n";
+        text += &self.create_action_struct();
+        text += "\n";
+        text += &self.create_tree_node_struct();
+        text += "\n";
+        text += &self.create_error_enum();
+        text += "\n";
+        return text;
+    }
 
     fn create_token_struct(&self) -> String {
         stringify!(
@@ -392,55 +1529,295 @@ impl CodeGen {
                 pub lexeme: String,
                 pub line: usize,
                 pub start_col: usize,
+                pub end_line: usize,
                 pub end_col: usize,
+                pub start_offset: usize,
+                pub end_offset: usize,
                 pub symbol: Symbol,
             }
 
             impl Token {
-                pub fn new(lexeme: String, start_col: usize, end_col: usize, symbol: Symbol) -> Token
+                pub fn new(lexeme: String, symbol: Symbol, line: usize, start_col: usize, start_offset: usize) -> Token
                 {
-                    Token { lexeme: lexeme, line: 0, start_col: start_col, end_col: end_col, symbol: symbol }
+                    let mut end_line = line;
+                    let mut end_col = start_col;
+                    for c in lexeme.chars() {
+                        if c == '\n' {
+                            end_line += 1;
+                            end_col = 1;
+                        } else {
+                            end_col += 1;
+                        }
+                    }
+                    let end_offset = start_offset + lexeme.len();
+                    Token { lexeme: lexeme, line: line, start_col: start_col, end_line: end_line, end_col: end_col, start_offset: start_offset, end_offset: end_offset, symbol: symbol }
                 }
             }
         ).to_string()
     }
 
-    fn create_symbol_struct(&self) -> String {
-        stringify!(
-            #[derive(Eq, Hash, PartialEq, Clone, Debug, PartialOrd, Ord)]
-            pub struct Symbol {
-                pub name: String,
-                pub is_terminal: bool,
+    // The variants of Terminal and NonTerminal are in the order of the names in TERMINALS and
+    // NONTERMINALS, so that a variant converted to usize is its id in the parse tables.
+    fn create_symbol_enums(&self) -> String {
+        let terminals = self.terminal_names();
+        let nonterminals = self.nonterminal_names();
+        let names = |names: &Vec<String>| names.iter().map(|name| format!("{:?}", name)).collect::<Vec<String>>().join(", ");
+        let mut text = format!("static TERMINALS: [&str; {}] = [{}];\n", terminals.len(), names(&terminals));
+        text += &format!("static NONTERMINALS: [&str; {}] = [{}];\n\n", nonterminals.len(), names(&nonterminals));
+
+        text += "#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug, PartialOrd, Ord)]\npub enum Terminal {\n";
+        for variant in CodeGen::to_variant_names(&terminals) {
+            text += &format!("\t{},\n", variant);
+        }
+        text += "}\n\n";
+        text += "#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug, PartialOrd, Ord)]\npub enum NonTerminal {\n";
+        for variant in CodeGen::to_variant_names(&nonterminals) {
+            text += &format!("\t{},\n", variant);
+        }
+        text += "}\n\n";
+
+        text += stringify!(
+            impl Terminal {
+                // The name of the lexer rule, or the literal in quotes
+                pub fn name(&self) -> &'static str
+                {
+                    TERMINALS[*self as usize]
+                }
+            }
+
+            impl NonTerminal {
+                pub fn name(&self) -> &'static str
+                {
+                    NONTERMINALS[*self as usize]
+                }
+            }
+
+            #[derive(Eq, Hash, PartialEq, Clone, Copy, Debug, PartialOrd, Ord)]
+            pub enum Symbol {
+                Terminal(Terminal),
+                NonTerminal(NonTerminal),
             }
 
             impl Symbol {
-            pub fn eof_symbol() -> Symbol
-            {
-                    Symbol { name: "eof".to_string(), is_terminal: true }
+                pub fn name(&self) -> &'static str
+                {
+                    match self {
+                        Symbol::Terminal(terminal) => terminal.name(),
+                        Symbol::NonTerminal(nonterminal) => nonterminal.name(),
+                    }
+                }
+
+                pub fn is_terminal(&self) -> bool
+                {
+                    matches!(self, Symbol::Terminal(_))
+                }
             }
+        );
+        text += "\n";
+        return text;
+    }
+
+    // The named lexer rules, the literals and the terminals of the grammar in the order of their names.
+    // eof and error are always there.
+    fn terminal_names(&self) -> Vec<String>
+    {
+        let mut terminals: BTreeSet<String> = self.grammar_gen.action_table.keys().map(|(_, symbol)| symbol.name.to_string()).collect();
+        terminals.extend(self.table.accepting_states.values().filter(|name| !name.is_empty()).cloned());
+        for rule in self.rules_in_order() {
+            for prod in &rule.productions {
+                let prod: &Production = unsafe { &**prod };
+                terminals.extend(prod.prod.iter().filter(|symbol| symbol.is_terminal).map(|symbol| symbol.name.to_string()));
             }
-        ).to_string()
+        }
+        terminals.insert("eof".to_string());
+        terminals.insert(crate::grammar2::Symbol::ERROR_NAME.to_string());
+        return terminals.into_iter().collect();
     }
 
-    fn create_action_struct(&self) -> String {
-        stringify!(
-            #[derive(Debug)]
-            pub enum Action {
-                Shift(usize),
-                // LHS and length of production
-                Reduce(Symbol, usize),
-                Accept
+    fn nonterminal_names(&self) -> Vec<String>
+    {
+        let nonterminals: BTreeSet<String> = self.grammar_gen.non_terminals.iter().map(|symbol| symbol.name.to_string()).collect();
+        return nonterminals.into_iter().collect();
+    }
+
+    // Maps each terminal name to the name of its variant in Terminal
+    fn terminal_variants(&self) -> HashMap<String, String>
+    {
+        let terminals = self.terminal_names();
+        let variants = CodeGen::to_variant_names(&terminals);
+        return terminals.into_iter().zip(variants).collect();
+    }
+
+    // Maps each rule name to the name of its variant in NonTerminal, which is also the name of its type in the syntax tree
+    fn nonterminal_variants(&self) -> HashMap<String, String>
+    {
+        let nonterminals = self.nonterminal_names();
+        let variants = CodeGen::to_variant_names(&nonterminals);
+        return nonterminals.into_iter().zip(variants).collect();
+    }
+
+    // Converts the names into distinct variant names. eof and error are named first, then the
+    // rules, then the literals and the helper rules, and a name that is already taken gets a number after it.
+    fn to_variant_names(names: &Vec<String>) -> Vec<String>
+    {
+        let rank = |name: &String| match name.as_str() {
+            "eof" | crate::grammar2::Symbol::ERROR_NAME => 0,
+            _ if name.ends_with('"') => 2,
+            _ if CodeGen::is_helper_name(name) => 3,
+            _ => 1,
+        };
+        let mut order: Vec<usize> = (0..names.len()).collect();
+        order.sort_by_key(|index| rank(&names[*index]));
+
+        // Self is a keyword
+        let mut taken: BTreeSet<String> = BTreeSet::new();
+        taken.insert("Self".to_string());
+        let mut variants = vec![String::new(); names.len()];
+        for index in order {
+            let mut variant = if names[index].ends_with('"') {
+                CodeGen::literal_to_type_name(&names[index])
+            } else if CodeGen::is_helper_name(&names[index]) {
+                CodeGen::helper_to_type_name(&names[index])
+            } else {
+                CodeGen::to_type_name(&names[index])
+            };
+            if !variant.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                variant = format!("Literal{}", variant);
             }
-        ).to_string()
+            variants[index] = CodeGen::take_unique_name(variant, &mut taken);
+        }
+        return variants;
+    }
+
+    // The names of the rules that the file parser adds for groups and operators, such as ("," expr)*
+    fn is_helper_name(name: &str) -> bool
+    {
+        return !name.ends_with('"') && (name.starts_with('(') || name.ends_with(|c: char| "?*+".contains(c)));
+    }
+
+    // Converts a helper rule name such as ("," expr)* into a type name such as CommaExprStar
+    fn helper_to_type_name(name: &str) -> String
+    {
+        let chars: Vec<char> = name.chars().collect();
+        let mut type_name = String::new();
+        let mut index = 0;
+        while index < chars.len() {
+            let start = index;
+            match chars[index] {
+                '"' => {
+                    // A literal ends at the next quote that is not escaped
+                    index += 1;
+                    while index < chars.len() && chars[index] != '"' {
+                        index += if chars[index] == '\\' { 2 } else { 1 };
+                    }
+                    index = usize::min(index + 1, chars.len());
+                    type_name += &CodeGen::literal_to_type_name(&chars[start..index].iter().collect::<String>());
+                    continue;
+                },
+                '?' => type_name += "Opt",
+                '*' => type_name += "Star",
+                '+' => type_name += "Plus",
+                '|' => type_name += "Or",
+                '(' | ')' | ' ' => (),
+                _ => {
+                    while index < chars.len() && !" ()|?*+\"".contains(chars[index]) {
+                        index += 1;
+                    }
+                    type_name += &CodeGen::to_type_name(&chars[start..index].iter().collect::<String>());
+                    continue;
+                },
+            }
+            index += 1;
+        }
+        return type_name;
+    }
+
+    // Adds a number after the name if it is taken and marks the result as taken
+    fn take_unique_name(name: String, taken: &mut BTreeSet<String>) -> String
+    {
+        let mut number = 2;
+        let mut unique = name.clone();
+        while taken.contains(&unique) {
+            unique = format!("{}{}", name, number);
+            number += 1;
+        }
+        taken.insert(unique.clone());
+        return unique;
     }
 
-    fn create_stack_symbol_struct(&self) -> String
+    // Converts a literal such as "+=" into a type name such as PlusEqual
+    fn literal_to_type_name(literal: &str) -> String
     {
+        let text = literal.strip_prefix('"').unwrap_or(literal);
+        let text = text.strip_suffix('"').unwrap_or(text);
+        let mut type_name = String::new();
+        let mut capitalize = true;
+        let mut chars = text.chars();
+        while let Some(mut c) = chars.next() {
+            // Escaped characters are named after the character
+            if c == '\\' {
+                c = chars.next().unwrap_or('\\');
+            }
+            if c.is_ascii_alphanumeric() {
+                if capitalize {
+                    type_name.push(c.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    type_name.push(c);
+                }
+                continue;
+            }
+            capitalize = true;
+            type_name += &match c {
+                '+' => "Plus".to_string(),
+                '-' => "Minus".to_string(),
+                '*' => "Star".to_string(),
+                '/' => "Slash".to_string(),
+                '%' => "Percent".to_string(),
+                '^' => "Caret".to_string(),
+                '=' => "Equal".to_string(),
+                '<' => "Less".to_string(),
+                '>' => "Greater".to_string(),
+                '!' => "Bang".to_string(),
+                '?' => "Question".to_string(),
+                ':' => "Colon".to_string(),
+                ';' => "Semicolon".to_string(),
+                ',' => "Comma".to_string(),
+                '.' => "Dot".to_string(),
+                '(' => "LParen".to_string(),
+                ')' => "RParen".to_string(),
+                '[' => "LBracket".to_string(),
+                ']' => "RBracket".to_string(),
+                '{' => "LBrace".to_string(),
+                '}' => "RBrace".to_string(),
+                '&' => "Amp".to_string(),
+                '|' => "Pipe".to_string(),
+                '~' => "Tilde".to_string(),
+                '#' => "Hash".to_string(),
+                '@' => "At".to_string(),
+                '$' => "Dollar".to_string(),
+                '\'' => "Quote".to_string(),
+                '"' => "DoubleQuote".to_string(),
+                '\\' => "Backslash".to_string(),
+                '`' => "Backtick".to_string(),
+                '_' => "Underscore".to_string(),
+                ' ' => "Space".to_string(),
+                '\t' => "Tab".to_string(),
+                '\n' => "Newline".to_string(),
+                _ => format!("U{:X}", c as u32),
+            };
+        }
+        return type_name;
+    }
+
+    fn create_action_struct(&self) -> String {
         stringify!(
-            enum StackSymbol {
-                Symbol(Symbol),
-                State(usize),
-                DollarSign,
+            #[derive(Debug, Clone, Copy)]
+            pub enum Action {
+                Shift(usize),
+                // Production id
+                Reduce(usize),
+                Accept,
             }
         ).to_string()
     }
@@ -453,6 +1830,46 @@ impl CodeGen {
                 pub token: Token,
                 pub children: Vec<TreeNode>,
             }
+
+            impl TreeNode {
+                // The node of a rule spans from the start of its first child to the end of its last child.
+                // Empty children are skipped, and a node without other children is empty at the lookahead.
+                fn from_children(lhs: NonTerminal, children: Vec<TreeNode>, lookahead: &Token) -> TreeNode {
+                    let mut non_empty = children.iter().filter(|child| !child.is_empty());
+                    let (start, end) = match non_empty.next() {
+                        Some(first) => {
+                            let last = &non_empty.last().unwrap_or(first).token;
+                            ((first.token.line, first.token.start_col, first.token.start_offset), (last.end_line, last.end_col, last.end_offset))
+                        },
+                        None => {
+                            let position = (lookahead.line, lookahead.start_col, lookahead.start_offset);
+                            (position, position)
+                        },
+                    };
+                    let token = Token {
+                        lexeme: lhs.name().to_string(),
+                        line: start.0,
+                        start_col: start.1,
+                        end_line: end.0,
+                        end_col: end.1,
+                        start_offset: start.2,
+                        end_offset: end.2,
+                        symbol: Symbol::NonTerminal(lhs),
+                    };
+                    TreeNode { token: token, children: children }
+                }
+
+                // True if the node does not cover any of the input
+                pub fn is_empty(&self) -> bool {
+                    self.token.start_offset == self.token.end_offset
+                }
+            }
+
+            impl std::fmt::Display for TreeNode {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}", self.token.lexeme)
+                }
+            }
         ).to_string()
     }
 
@@ -461,7 +1878,11 @@ impl CodeGen {
         stringify!(
             #[derive(Debug)]
             pub enum ErrorKind {
-                GrammarParseFailed,
+                // The first token that the grammar does not accept
+                GrammarParseFailed(SyntaxError),
+                // The input of a Lexer could not be read or is not UTF-8
+                ReadFailed(std::io::ErrorKind),
+                // Line and column of the first character that could not be tokenized
                 TokenizationFailed(usize, usize),
             }
 
@@ -469,11 +1890,50 @@ impl CodeGen {
                 pub fn get_err_message(&self) -> String
                 {
                     return match self  {
-                        Self::GrammarParseFailed => "Error: the token sequence is not accepted by the grammar".to_string(),
-                        Self::TokenizationFailed(start, end) => format!("Error: unable to tokenize the sequence of characters starting at {} and ending at {}", start, end),
+                        Self::GrammarParseFailed(error) => error.get_err_message(),
+                        Self::ReadFailed(kind) => format!("Error: unable to read the input: {}", kind),
+                        Self::TokenizationFailed(line, column) => format!("Error: unable to tokenize the characters starting at line {} column {}", line, column),
                     }
                 }
             }
+
+            // Characters that could not be tokenized, as a token named error
+            #[derive(Debug, Clone)]
+            pub struct LexicalError {
+                pub token: Token,
+            }
+
+            impl LexicalError {
+                pub fn get_err_message(&self) -> String
+                {
+                    return format!("Error: unable to tokenize {:?} at {}:{}", self.token.lexeme, self.token.line, self.token.start_col);
+                }
+            }
+
+            // A token that the parser could not accept and the terminals that it could have accepted instead
+            #[derive(Debug, Clone)]
+            pub struct SyntaxError {
+                pub token: Token,
+                pub expected: &'static [Terminal],
+            }
+
+            impl SyntaxError {
+                fn new(state: usize, token: Token) -> SyntaxError
+                {
+                    SyntaxError { token: token, expected: get_expected_terminals(state) }
+                }
+
+                pub fn get_err_message(&self) -> String
+                {
+                    let found = format!("`{}` at {}:{}", self.token.symbol.name(), self.token.line, self.token.start_col);
+                    let names: Vec<String> = self.expected.iter().map(|terminal| format!("`{}`", terminal.name())).collect();
+                    return match names.split_last() {
+                        None => format!("Error: unexpected {}", found),
+                        Some((last, [])) => format!("Error: expected {}, found {}", last, found),
+                        Some((last, rest)) => format!("Error: expected {} or {}, found {}", rest.join(", "), last, found),
+                    };
+                }
+            }
         ).to_string()
     }
 
@@ -11,20 +11,21 @@ use crate::nfa::Transition;
 use crate::nfa::{NFA, NFANode, TransitionKind};
 
 pub struct DFABuilder {
-    pub nodes: HashMap<String, Rc<Mutex<DFANode>>>, // Map Node ID to backing store
     pub raw_nodes: HashMap<String, *mut DFANode>,
 }
 
 #[derive(Debug)]
 pub enum DFANodeKind {
     Nonacccept,
-    Accept(String),
+    // Token name and priority of the accepted rule
+    Accept(String, i32),
 }
 
 pub struct DFANode {
+    // The IDs of the NFA nodes this node was built from, only kept for debugging
+    #[allow(dead_code)]
     pub states: BTreeSet<i32>,
     pub nodes: Vec<Rc<Mutex<NFANode>>>,
-    pub transitions: HashMap<TransitionKind, Rc<Mutex<DFANode>>>,
     pub raw_transitions: HashMap<TransitionKind, *mut DFANode>,
     pub kind: DFANodeKind,
 }
@@ -32,13 +33,7 @@ pub struct DFANode {
 impl DFANode {
     pub fn new(states: BTreeSet<i32>, nodes: Vec<Rc<Mutex<NFANode>>>, kind: DFANodeKind) -> DFANode
     {
-        DFANode { states: states, nodes, transitions: HashMap::new(), raw_transitions: HashMap::new(), kind}
-    }
-
-    pub fn print(node: Rc<Mutex<DFANode>>)
-    {
-        let locked = node.lock().unwrap();
-        println!("{:?}", locked.states);
+        DFANode { states: states, nodes, raw_transitions: HashMap::new(), kind}
     }
 }
 
@@ -47,7 +42,6 @@ impl DFABuilder {
     pub unsafe fn convert_nfa_to_dfa(nfa: NFA) -> *mut DFANode
     {
         let mut dfa_builder = DFABuilder {
-            nodes: HashMap::new(),
             raw_nodes: HashMap::new(),
         };
         return dfa_builder.convert_nfa_to_dfa_raw(nfa);
@@ -127,11 +121,11 @@ impl DFABuilder {
                 match &locked.kind {
                     NFANodeKind::EndWithToken(token) => {
                         min_priority = priority;
-                        kind = DFANodeKind::Accept(token.to_string());
+                        kind = DFANodeKind::Accept(token.to_string(), priority);
                     },
                     NFANodeKind::End => {
                         min_priority = priority;
-                        kind = DFANodeKind::Accept("".to_string());
+                        kind = DFANodeKind::Accept("".to_string(), priority);
                     },
                     _ => (),
                 }
@@ -215,8 +209,8 @@ impl DFABuilder {
     {
         let locked_node = node.lock().unwrap();
         let kind = match &locked_node.kind {
-            NFANodeKind::EndWithToken(token) => DFANodeKind::Accept(token.to_string()),
-            NFANodeKind::End => DFANodeKind::Accept("".to_string()),
+            NFANodeKind::EndWithToken(token) => DFANodeKind::Accept(token.to_string(), i32::MAX),
+            NFANodeKind::End => DFANodeKind::Accept("".to_string(), i32::MAX),
             _ => DFANodeKind::Nonacccept,
         };
         let mut states: BTreeSet<i32> = BTreeSet::new();
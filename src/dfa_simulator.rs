@@ -27,7 +27,7 @@ impl DFASimulator {
             else {
                 // If we reached the end of the DFA and arrived at an acceptance state
                 println!("here");
-                if let crate::dfa_builder::DFANodeKind::Accept(token) = &(*next).kind 
+                if let crate::dfa_builder::DFANodeKind::Accept(token, _) = &(*next).kind 
                 {
                     if !token.is_empty() {
                         tokens.push(Symbol{name: token.to_string(), is_terminal: true, emptiness: crate::grammar2::Empty::NonEmpty});
@@ -47,7 +47,7 @@ impl DFASimulator {
         //println!("{:?}", (*next).kind);
 
         // Get last node
-        if let crate::dfa_builder::DFANodeKind::Accept(token) = &(*next).kind 
+        if let crate::dfa_builder::DFANodeKind::Accept(token, _) = &(*next).kind 
         {
             if !token.is_empty() {
                 tokens.push(Symbol {name: token.to_string(), is_terminal: true, emptiness: crate::grammar2::Empty::NonEmpty});
@@ -84,7 +84,7 @@ impl DFASimulator {
         }
 
         // Get last node
-        if let crate::dfa_builder::DFANodeKind::Accept(_) = &(*next).kind 
+        if let crate::dfa_builder::DFANodeKind::Accept(_, _) = &(*next).kind 
         {
             return true;
         } else {
@@ -5,8 +5,10 @@ use std::io::{BufReader, BufRead};
 
 use colored::Colorize;
 
-use crate::NFA;
-use crate::grammar2::{Production, Symbol, GrammarRule, Empty};
+use crate::nfa::NFA;
+use crate::error::RlpgErr;
+use crate::regex_parser::RegExParser;
+use crate::grammar2::{Production, Symbol, GrammarRule, HelperKind, Empty, Associativity, Precedence};
 
 #[derive(Debug, PartialEq)]
 pub enum FileParserErrorKind {
@@ -30,19 +32,31 @@ pub enum FileParserErrorKind {
     InvalidIdentifier,
     DuplicateProduction,
     RootRuleDoesNotExist,
+    InvalidDeclaration,
+    NoLexerSection,
+    InvalidStringLiteral,
+    MisplacedCodeSection,
 }
 
 #[derive(Debug)]
 pub struct FileParserError {
     pub kind: FileParserErrorKind,
     pub inner_error: Option<Box<dyn Error>>,
+    // The line that was being read when the error was found, starting at 1
+    pub line: Option<usize>,
 }
 
 impl FileParserError {
     pub fn new(kind: FileParserErrorKind, error: Option<Box<dyn Error>>) -> FileParserError {
-        return FileParserError { kind, inner_error: error };
+        return FileParserError { kind, inner_error: error, line: None };
     }
 
+    pub fn with_line(kind: FileParserErrorKind, line: usize) -> FileParserError {
+        return FileParserError { kind, inner_error: None, line: Some(line) };
+    }
+}
+
+impl RlpgErr for FileParserError {
     fn get_err_message(&self) -> String {
         let msg = match self.kind {
             FileParserErrorKind::FileDoesNotBeginWithSectionHeader => "The input file does not begin with a section header",
@@ -65,6 +79,10 @@ impl FileParserError {
             FileParserErrorKind::DuplicateProduction => "The grammar rule contains duplicate productions.",
             FileParserErrorKind::RootRuleDoesNotExist => "A grammar rule with the name of 'root' does not exist.",
             FileParserErrorKind::NoGrammarSection => "The file does not contain a grammar section, which is required.",
+            FileParserErrorKind::InvalidDeclaration => "The declaration in the grammar section is invalid.",
+            FileParserErrorKind::NoLexerSection => "The file does not contain a lexer section after the code section, which is required.",
+            FileParserErrorKind::InvalidStringLiteral => "The string literal is empty or is missing the closing quote.",
+            FileParserErrorKind::MisplacedCodeSection => "The code section must be the first section of the file.",
         };
         return msg.to_string();
     }
@@ -72,7 +90,10 @@ impl FileParserError {
 
 impl std::fmt::Display for FileParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let msg = format!("Error: {}", self.get_err_message()).red();
+        let msg = match self.line {
+            Some(line) => format!("Error on line {}: {}", line, self.get_err_message()).red(),
+            None => format!("Error: {}", self.get_err_message()).red(),
+        };
         let result = writeln!(f, "{}", msg);
         
         if let Some(error) = &self.inner_error {
@@ -112,6 +133,7 @@ pub struct Rule {
 }
 
 enum FileSection {
+    Code,
     Lexer,
     Grammar,
 }
@@ -119,6 +141,7 @@ enum FileSection {
 impl FileSection {
     fn as_str(&self) -> &str {
         return match self {
+            Self::Code => "CODE",
             Self::Lexer => "LEXER",
             Self::Grammar => "GRAMMAR",
         }
@@ -127,32 +150,58 @@ impl FileSection {
 
 pub struct FileParser {
     curr_section: FileSection,
+    // Contents of the code section
+    pub code: Option<String>,
     symbols: HashMap<String, bool>,
     pub grammar_rules: Vec<GrammarRule>,
+    // Maps grammar rule names to the Rust type of their semantic value
+    pub value_types: HashMap<String, String>,
+    // Maps the rules named in %type declarations to the line of their declaration
+    type_lines: HashMap<String, usize>,
+    // Maps terminal names to the precedence given by %left, %right and %nonassoc
+    pub precedences: HashMap<String, Precedence>,
+    // True if %ast asks for the typed syntax tree
+    pub ast: bool,
+    precedence_level: usize,
+    // Number of lines read so far
+    line: usize,
     emptiness_info: HashMap<String, Empty>,
     rules: Vec<Rule>,
-    undefined_symbols: HashSet<String>,
+    // String literals used in the grammar section, in order of first appearance
+    literals: Vec<String>,
+    // Rules for the operators and groups in productions, which are added after the rules of the file
+    helper_rules: Vec<GrammarRule>,
+    // Maps the symbols that are used before they are defined to the line of their first use
+    undefined_symbols: HashMap<String, usize>,
 }
 
 impl FileParser {
     pub fn new() -> FileParser {
         return FileParser {
-            curr_section: FileSection::Lexer,
+            curr_section: FileSection::Code,
+            code: None,
             symbols: HashMap::new(),
             grammar_rules: Vec::new(),
+            value_types: HashMap::new(),
+            type_lines: HashMap::new(),
+            precedences: HashMap::new(),
+            ast: false,
+            precedence_level: 0,
+            line: 0,
             emptiness_info: HashMap::new(),
             rules: Vec::new(),
-            undefined_symbols: HashSet::new(),
+            literals: Vec::new(),
+            helper_rules: Vec::new(),
+            undefined_symbols: HashMap::new(),
         };
     }
 
     fn is_valid_section_header(&self, line: &str) -> bool {
-        if let FileSection::Lexer = self.curr_section {
-            return line == "SECTION LEXER";
-        }
-        else {
-            return line == "SECTION GRAMMAR"; 
-        }
+        return line == format!("SECTION {}", self.curr_section.as_str());
+    }
+
+    fn is_code_section_header(line: &str) -> bool {
+        return line == format!("SECTION {}", FileSection::Code.as_str());
     }
 
     pub fn parse_file(&mut self, path: &str) -> Result<(), FileParserError> {
@@ -161,15 +210,49 @@ impl FileParser {
             Err(error) => return Err(FileParserError::new(FileParserErrorKind::FileOpenError, Some(Box::new(error)))),
         };
 
-        let mut reader = BufReader::new(file);
+        return self.parse_reader(BufReader::new(file));
+    }
+
+    // Parses a spec given as text, e.g. from include_str!
+    pub fn parse_str(&mut self, source: &str) -> Result<(), FileParserError> {
+        return self.parse_reader(source.as_bytes());
+    }
+
+    pub fn parse_reader<R: BufRead>(&mut self, reader: R) -> Result<(), FileParserError> {
+        self.line = 0;
+        return self.parse_sections(reader).map_err(|mut error| {
+            if error.line.is_none() {
+                error.line = Some(self.line);
+            }
+            error
+        });
+    }
 
+    // Reads a line and counts it so that errors know their line
+    fn read_line<R: BufRead>(&mut self, reader: &mut R, line: &mut String) -> std::io::Result<usize> {
+        let result = reader.read_line(line);
+        if let Ok(1..) = result {
+            self.line += 1;
+        }
+        return result;
+    }
+
+    fn parse_sections<R: BufRead>(&mut self, mut reader: R) -> Result<(), FileParserError> {
         let mut line: String = String::new();
-        if let Err(error) = reader.read_line(&mut line) {
+        if let Err(error) = self.read_line(&mut reader, &mut line) {
             return Err(FileParserError::new(FileParserErrorKind::ReadLineError, Some(Box::new(error))));
         }
 
-        if !self.is_valid_section_header(line.trim()) {
-            return Err(FileParserError::new(FileParserErrorKind::FileDoesNotBeginWithSectionHeader, None));
+        // The code section is optional
+        self.curr_section = FileSection::Code;
+        if self.is_valid_section_header(line.trim()) {
+            self.code = Some(self.parse_code_section(&mut reader)?);
+        }
+        else {
+            self.curr_section = FileSection::Lexer;
+            if !self.is_valid_section_header(line.trim()) {
+                return Err(FileParserError::new(FileParserErrorKind::FileDoesNotBeginWithSectionHeader, None));
+            }
         }
         self.curr_section = FileSection::Grammar;
 
@@ -178,7 +261,7 @@ impl FileParser {
         let mut rules: Vec<Rule> = Vec::new();
         let mut rule_counter = 1;
         let mut found_grammar_section = false;
-        while let Ok(result) = reader.read_line(&mut line) {
+        while let Ok(result) = self.read_line(&mut reader, &mut line) {
             if result == 0 {
                 break;
             }
@@ -193,6 +276,9 @@ impl FileParser {
                 found_grammar_section = true;
                 break;
             }
+            if FileParser::is_code_section_header(&trimmed) {
+                return Err(FileParserError::new(FileParserErrorKind::MisplacedCodeSection, None));
+            }
 
             let rule = FileParser::parse_rule(&line);
             if rule.is_err() {
@@ -218,7 +304,7 @@ impl FileParser {
         }
 
         if !found_grammar_section {
-            return Err(FileParserError { kind: FileParserErrorKind::NoGrammarSection, inner_error: None });
+            return Err(FileParserError::new(FileParserErrorKind::NoGrammarSection, None));
         }
 
         // Parse grammar
@@ -227,12 +313,47 @@ impl FileParser {
             return Err(result.err().unwrap());
         }
         self.grammar_rules = result.unwrap();
-        
 
-        self.rules = rules;
+        // String literals take precedence over the rules in the lexer section
+        let mut all_rules: Vec<Rule> = Vec::new();
+        for literal in &self.literals {
+            all_rules.push(Rule {
+                kind: RuleKind::Named(FileParser::get_literal_name(literal)),
+                regex: RegExParser::escape_literal(literal),
+                action: None,
+                priority: 1,
+            });
+        }
+        all_rules.extend(rules);
+        for (index, rule) in all_rules.iter_mut().enumerate() {
+            rule.priority = index as i32 + 1;
+        }
+
+        self.rules = all_rules;
         return Ok(());
     }
 
+    fn parse_code_section<R: BufRead>(&mut self, reader: &mut R) -> Result<String, FileParserError> {
+        // Everything until the lexer section header is kept as is
+        self.curr_section = FileSection::Lexer;
+        let mut code = String::new();
+        let mut line = String::new();
+        loop {
+            match self.read_line(reader, &mut line) {
+                Ok(0) => break,
+                Ok(_) => (),
+                Err(error) => return Err(FileParserError::new(FileParserErrorKind::ReadLineError, Some(Box::new(error)))),
+            }
+
+            if self.is_valid_section_header(line.trim()) {
+                return Ok(code);
+            }
+            code.push_str(&line);
+            line.clear();
+        }
+        return Err(FileParserError::new(FileParserErrorKind::NoLexerSection, None));
+    }
+
     fn parse_rule(line: &str) -> Result<Rule, FileParserError> {
         let parts = FileParser::parse_line(line);
 
@@ -292,6 +413,9 @@ impl FileParser {
         else if name == "root" {
             return Err(FileParserError::new(FileParserErrorKind::InvalidRuleName, None));
         }
+        else if name == Symbol::ERROR_NAME {
+            return Err(FileParserError::new(FileParserErrorKind::InvalidRuleName, None));
+        }
 
         if name == "unnamed" {
             return Ok(RuleKind::Unnamed);
@@ -329,7 +453,7 @@ impl FileParser {
         return parts;
     }
 
-    fn parse_grammar_section(&mut self, reader: &mut BufReader<File>) -> Result<Vec<GrammarRule>, FileParserError>
+    fn parse_grammar_section<R: BufRead>(&mut self, reader: &mut R) -> Result<Vec<GrammarRule>, FileParserError>
     {
         // Parse each rule until the end
         let mut line = String::new();
@@ -338,10 +462,14 @@ impl FileParser {
         let mut in_middle_of_rule = false;
         let mut root_rule_exists = false;
 
-        while let Ok(result) = reader.read_line(&mut line) {
+        while let Ok(result) = self.read_line(reader, &mut line) {
             if result == 0 {
                 break;
             }
+
+            if FileParser::is_code_section_header(line.trim()) {
+                return Err(FileParserError::new(FileParserErrorKind::MisplacedCodeSection, None));
+            }
             
             let chars: Vec<char> = line.chars().collect();
 
@@ -372,7 +500,7 @@ impl FileParser {
                     let t = prev_rule.take().unwrap();
                     if self.does_rule_contain_duplicate_prods(&t)
                     {
-                        return Err(FileParserError { kind: FileParserErrorKind::DuplicateProduction, inner_error: None });
+                        return Err(FileParserError::new(FileParserErrorKind::DuplicateProduction, None));
                     }
                     if t.name == "root" {
                         root_rule_exists = true;
@@ -381,6 +509,9 @@ impl FileParser {
                     prev_rule = None;
                 }
             }
+            else if line.trim_start().starts_with('%') {
+                self.parse_declaration(&chars)?;
+            }
             else {
                 // Read first production
                 let first_prod = self.parse_first_prod(&chars);
@@ -404,19 +535,163 @@ impl FileParser {
         if rules.len() == 0 {
             return Err(FileParserError::new(FileParserErrorKind::NoGrammarRules, None))
         }
+        rules.extend(self.helper_rules.drain(..));
+
+        // Point at the first use of an unknown symbol
+        if let Some(line) = self.undefined_symbols.values().min() {
+            return Err(FileParserError::with_line(FileParserErrorKind::UnknownSymbol, *line));
+        }
 
         // Ensure one rule called root exists
         if !root_rule_exists {
-            return Err(FileParserError { kind: FileParserErrorKind::RootRuleDoesNotExist, inner_error: None });
+            return Err(FileParserError::new(FileParserErrorKind::RootRuleDoesNotExist, None));
         }
 
-        if self.undefined_symbols.len() > 0 {
-            return Err(FileParserError::new(FileParserErrorKind::UnknownSymbol, None));
+        // Only grammar rules can be given a type
+        for name in self.value_types.keys() {
+            let line = self.type_lines[name];
+            match self.symbols.get(name) {
+                Some(false) => (),
+                Some(true) => return Err(FileParserError::with_line(FileParserErrorKind::InvalidDeclaration, line)),
+                None => return Err(FileParserError::with_line(FileParserErrorKind::UnknownSymbol, line)),
+            }
+        }
+
+        // Only terminals can be given a precedence
+        for name in self.precedences.keys() {
+            if let Some(false) = self.symbols.get(name) {
+                return Err(FileParserError::new(FileParserErrorKind::InvalidDeclaration, None));
+            }
         }
 
         return Ok(rules);
     }
 
+    fn parse_declaration(&mut self, line: &Vec<char>) -> Result<(), FileParserError>
+    {
+        let mut line_index: usize = 0;
+        let keyword = match self.parse_identifier(&line, &mut line_index) {
+            Some(value) => value,
+            None => return Err(FileParserError::new(FileParserErrorKind::InvalidDeclaration, None)),
+        };
+
+        return match keyword.as_str() {
+            "%type" => self.parse_type_declaration(line, &mut line_index),
+            "%left" => self.parse_precedence_declaration(line, &mut line_index, Associativity::Left),
+            "%right" => self.parse_precedence_declaration(line, &mut line_index, Associativity::Right),
+            "%nonassoc" => self.parse_precedence_declaration(line, &mut line_index, Associativity::NonAssoc),
+            "%ast" => self.parse_ast_declaration(line, &mut line_index),
+            _ => Err(FileParserError::new(FileParserErrorKind::InvalidDeclaration, None)),
+        };
+    }
+
+    fn parse_type_declaration(&mut self, line: &Vec<char>, line_index: &mut usize) -> Result<(), FileParserError>
+    {
+        // Read the type between < and >
+        let value_type = self.parse_type(&line, line_index)?;
+
+        // Read the names of the rules with this type
+        let mut has_names = false;
+        while let Some(name) = self.parse_identifier(&line, line_index) {
+            if !FileParser::is_identifier_valid(&name) {
+                return Err(FileParserError::with_line(FileParserErrorKind::InvalidIdentifier, self.line));
+            }
+            self.type_lines.insert(name.clone(), self.line);
+            if let Some(_) = self.value_types.insert(name, value_type.to_string()) {
+                return Err(FileParserError::with_line(FileParserErrorKind::InvalidDeclaration, self.line));
+            }
+            has_names = true;
+        }
+
+        if !has_names {
+            return Err(FileParserError::with_line(FileParserErrorKind::InvalidDeclaration, self.line));
+        }
+        return Ok(());
+    }
+
+    fn parse_ast_declaration(&mut self, line: &Vec<char>, line_index: &mut usize) -> Result<(), FileParserError>
+    {
+        // %ast takes no names and is only given once
+        if self.ast || line[*line_index..].iter().any(|c| !c.is_whitespace()) {
+            return Err(FileParserError::new(FileParserErrorKind::InvalidDeclaration, None));
+        }
+        self.ast = true;
+        return Ok(());
+    }
+
+    fn parse_precedence_declaration(&mut self, line: &Vec<char>, line_index: &mut usize, associativity: Associativity) -> Result<(), FileParserError>
+    {
+        // Each declaration has a higher precedence than the ones before it
+        self.precedence_level += 1;
+        let precedence = Precedence { level: self.precedence_level, associativity };
+
+        let mut has_names = false;
+        while let Some(name) = self.parse_precedence_name(&line, line_index)? {
+            if let Some(_) = self.precedences.insert(name, precedence) {
+                return Err(FileParserError::new(FileParserErrorKind::InvalidDeclaration, None));
+            }
+            has_names = true;
+        }
+
+        if !has_names {
+            return Err(FileParserError::new(FileParserErrorKind::InvalidDeclaration, None));
+        }
+        return Ok(());
+    }
+
+    // Reads a terminal name or string literal used by a precedence declaration or %prec.
+    // Names that are not terminals can be used to give a production a precedence with %prec.
+    fn parse_precedence_name(&mut self, line: &Vec<char>, line_index: &mut usize) -> Result<Option<String>, FileParserError>
+    {
+        if let Some(literal) = FileParser::parse_string_literal(&line, line_index)? {
+            return Ok(Some(self.add_literal(literal)));
+        }
+
+        if let Some(name) = self.parse_identifier(&line, line_index) {
+            if !FileParser::is_identifier_valid(&name) {
+                return Err(FileParserError::new(FileParserErrorKind::InvalidIdentifier, None));
+            }
+            return Ok(Some(name));
+        }
+        return Ok(None);
+    }
+
+    fn parse_type(&self, line: &Vec<char>, index: &mut usize) -> Result<String, FileParserError>
+    {
+        // Skip whitespace
+        while *index < line.len() && char::is_whitespace(line[*index]) {
+            *index += 1;
+        }
+
+        if *index >= line.len() || line[*index] != '<' {
+            return Err(FileParserError::with_line(FileParserErrorKind::InvalidDeclaration, self.line));
+        }
+        *index += 1;
+
+        // Types may contain angle brackets themselves, e.g. <Vec<String>>, and
+        // arrows, e.g. <Box<dyn Fn(i32) -> i32>>
+        let mut value_type = String::new();
+        let mut depth = 1;
+        while *index < line.len() {
+            let c = line[*index];
+            *index += 1;
+            if c == '<' {
+                depth += 1;
+            } else if c == '>' && !value_type.ends_with('-') {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            value_type.push(c);
+        }
+
+        if depth != 0 || value_type.trim().is_empty() {
+            return Err(FileParserError::with_line(FileParserErrorKind::InvalidDeclaration, self.line));
+        }
+        return Ok(value_type.trim().to_string());
+    }
+
     fn parse_first_prod(&mut self, line: &Vec<char>) -> Result<GrammarRule, FileParserError>
     {
         let name: String;
@@ -429,7 +704,7 @@ impl FileParser {
             return Err(FileParserError::new(FileParserErrorKind::EmptyLine, None));
         }
 
-        if !FileParser::is_identifier_valid(&name)
+        if !FileParser::is_identifier_valid(&name) || name == Symbol::ERROR_NAME
         {
             return Err(FileParserError::new(FileParserErrorKind::InvalidIdentifier, None));
         }
@@ -450,39 +725,14 @@ impl FileParser {
             // Throw a duplicate name error
             return Err(FileParserError::new(FileParserErrorKind::DuplicateGrammarRuleName, None));
         }
+        // Rules may be used before they are defined
+        self.undefined_symbols.remove(&name);
 
-        // Read production
-        let mut production: Vec<Symbol> = Vec::new();
-        while let Some(temp_name) = self.parse_identifier(&line, &mut line_index) {
-            // Check if symbol is defined
-            if let Some(is_terminal) = self.symbols.get(&temp_name) {
-                
-                if self.undefined_symbols.contains(&temp_name) {
-                    self.undefined_symbols.remove(&temp_name);
-                }
-                
-                // Get emptiness info
-                let emptiness = self.get_emptiness_or_default(&temp_name);
-                production.push(Symbol { name: temp_name, is_terminal: *is_terminal, emptiness });
-            }
-            else if !FileParser::is_identifier_valid(&temp_name) {
-                return Err(FileParserError::new(FileParserErrorKind::InvalidIdentifier, None));
-            }
-            else {
-                // Throw an error for undefined symbol
-                //return Err(FileParserError::new(FileParserErrorKind::UnknownSymbol, None));
-                production.push(Symbol { name: temp_name.clone(), is_terminal: false, emptiness: Empty::NonEmpty });
-                // Add to undefined list
-                self.undefined_symbols.insert(temp_name);
-            }
-        }
+        // Read production, which may be empty
+        let production = self.parse_production(&line, &mut line_index)?;
 
-        if production.len() == 0 {
-            return Err(FileParserError::new(FileParserErrorKind::InvalidGrammarRule, None));
-        }
-
-        let mut rule = GrammarRule { name, productions: Vec::new()};
-        rule.productions.push(Box::into_raw(Box::new(Production {prod: production })));
+        let mut rule = GrammarRule { name, productions: Vec::new(), helper: None };
+        rule.productions.push(Box::into_raw(Box::new(production)));
         return Ok(rule);
     }
 
@@ -496,34 +746,263 @@ impl FileParser {
         }
         line_index += 1;
 
-        // Read production
+        // Read production, which may be empty
+        let production = self.parse_production(&line, &mut line_index)?;
+        return Ok(Box::into_raw(Box::new(production)));
+    }
+
+    fn parse_production(&mut self, line: &Vec<char>, line_index: &mut usize) -> Result<Production, FileParserError>
+    {
+        let production = self.parse_sequence(&line, line_index)?;
+        let mut action = None;
+        let mut precedence = None;
+
+        // Check if the rest of the line is action code
+        if let Some(code) = FileParser::parse_production_action(&line, line_index) {
+            action = FileParser::get_action_code(code)?;
+        }
+        else if let Some(temp_name) = self.parse_identifier(&line, line_index) {
+            // The sequence only stops early at %prec, or at a | or ) outside of a group
+            if temp_name != "%prec" {
+                return Err(FileParserError::new(FileParserErrorKind::InvalidProduction, None));
+            }
+
+            // %prec must be the last part of the production before the action code
+            let name = match self.parse_precedence_name(&line, line_index)? {
+                Some(value) => value,
+                None => return Err(FileParserError::new(FileParserErrorKind::InvalidProduction, None)),
+            };
+            if !self.precedences.contains_key(&name) {
+                return Err(FileParserError::new(FileParserErrorKind::UnknownSymbol, None));
+            }
+            precedence = Some(name);
+
+            if let Some(code) = FileParser::parse_production_action(&line, line_index) {
+                action = FileParser::get_action_code(code)?;
+            }
+            else if let Some(_) = self.parse_identifier(&line, line_index) {
+                return Err(FileParserError::new(FileParserErrorKind::InvalidProduction, None));
+            }
+        }
+        return Ok(Production { prod: production, action, id: 0, precedence });
+    }
+
+    // Reads symbols until the end of the line, action code, %prec, or the | or ) of a group.
+    // Groups and symbols followed by ?, * or + are replaced with the symbols of helper rules.
+    fn parse_sequence(&mut self, line: &Vec<char>, line_index: &mut usize) -> Result<Vec<Symbol>, FileParserError>
+    {
         let mut production: Vec<Symbol> = Vec::new();
-        while let Some(temp_name) = self.parse_identifier(&line, &mut line_index) {
-            // Check if symbol is defined
-            if let Some(is_terminal) = self.symbols.get(&temp_name) {
-                if self.undefined_symbols.contains(&temp_name) {
-                    self.undefined_symbols.remove(&temp_name);
-                }
-                let emptiness = self.get_emptiness_or_default(&temp_name);
-                production.push(Symbol { name: temp_name, is_terminal: *is_terminal, emptiness });
+        loop {
+            // Check if the next symbol is a string literal
+            let mut symbol = if let Some(literal) = FileParser::parse_string_literal(&line, line_index)? {
+                let name = self.add_literal(literal);
+                Symbol { name, is_terminal: true, emptiness: Empty::NonEmpty }
             }
-            else if !FileParser::is_identifier_valid(&temp_name) {
-                return Err(FileParserError::new(FileParserErrorKind::InvalidIdentifier, None));
+            else if *line_index < line.len() && line[*line_index] == '(' {
+                *line_index += 1;
+                self.parse_group(&line, line_index)?
             }
             else {
-                // Throw an error for undefined symbol
-                //return Err(FileParserError::new(FileParserErrorKind::UnknownSymbol, None));
+                let start = *line_index;
+                match FileParser::parse_symbol_name(&line, line_index) {
+                    Some(name) if name != "%prec" && !name.starts_with('{') => self.get_production_symbol(name)?,
+                    _ => {
+                        *line_index = start;
+                        break;
+                    },
+                }
+            };
 
-                production.push(Symbol { name: temp_name.clone(), is_terminal: false, emptiness: Empty::NonEmpty });
-                // Add to undefined list
-                self.undefined_symbols.insert(temp_name);
+            // Operators are written right after the symbol
+            while *line_index < line.len() && ['?', '*', '+'].contains(&line[*line_index]) {
+                symbol = self.add_operator_rule(symbol, line[*line_index])?;
+                *line_index += 1;
             }
+            production.push(symbol);
         }
+        return Ok(production);
+    }
 
-        if production.len() == 0 {
-            return Err(FileParserError::new(FileParserErrorKind::InvalidProduction, None));
+    // Reads a name up to whitespace or the start of an operator or group
+    fn parse_symbol_name(line: &Vec<char>, index: &mut usize) -> Option<String>
+    {
+        while *index < line.len() && char::is_whitespace(line[*index]) {
+            *index += 1;
+        }
+
+        let mut name = String::new();
+        while *index < line.len() && !char::is_whitespace(line[*index]) && !":()|?*+".contains(line[*index]) {
+            name.push(line[*index]);
+            *index += 1;
+        }
+        if name.is_empty() {
+            return None;
+        }
+        return Some(name);
+    }
+
+    fn get_production_symbol(&mut self, name: String) -> Result<Symbol, FileParserError>
+    {
+        // The error terminal is shifted by the parser when it recovers from a syntax error
+        if name == Symbol::ERROR_NAME {
+            self.symbols.insert(name.clone(), true);
+            return Ok(Symbol { name, is_terminal: true, emptiness: Empty::NonEmpty });
+        }
+
+        // Check if symbol is defined
+        if let Some(is_terminal) = self.symbols.get(&name) {
+            let is_terminal = *is_terminal;
+            self.undefined_symbols.remove(&name);
+            let emptiness = self.get_emptiness_or_default(&name);
+            return Ok(Symbol { name, is_terminal, emptiness });
+        }
+        if !FileParser::is_identifier_valid(&name) {
+            return Err(FileParserError::new(FileParserErrorKind::InvalidIdentifier, None));
+        }
+
+        // Rules may be used before they are defined
+        self.undefined_symbols.entry(name.clone()).or_insert(self.line);
+        return Ok(Symbol { name, is_terminal: false, emptiness: Empty::NonEmpty });
+    }
+
+    // Reads the alternatives of a group after its ( and returns the symbol that stands for the group
+    fn parse_group(&mut self, line: &Vec<char>, line_index: &mut usize) -> Result<Symbol, FileParserError>
+    {
+        let mut alternatives = vec![self.parse_sequence(&line, line_index)?];
+        loop {
+            while *line_index < line.len() && char::is_whitespace(line[*line_index]) {
+                *line_index += 1;
+            }
+            match line.get(*line_index) {
+                Some('|') => {
+                    *line_index += 1;
+                    alternatives.push(self.parse_sequence(&line, line_index)?);
+                },
+                Some(')') => {
+                    *line_index += 1;
+                    break;
+                },
+                _ => return Err(FileParserError::new(FileParserErrorKind::InvalidProduction, None)),
+            }
+        }
+
+        // Parentheses around one symbol only group it
+        if alternatives.len() == 1 && alternatives[0].len() == 1 {
+            return Ok(alternatives.remove(0).remove(0));
+        }
+
+        let text: Vec<String> = alternatives.iter().map(|symbols| symbols.iter().map(|symbol| symbol.name.as_str()).collect::<Vec<&str>>().join(" ")).collect();
+        return self.add_helper_rule(format!("({})", text.join(" | ")), HelperKind::Group, alternatives);
+    }
+
+    // Adds the rule for x?, x* or x+, where x* and x+ are left recursive
+    fn add_operator_rule(&mut self, symbol: Symbol, operator: char) -> Result<Symbol, FileParserError>
+    {
+        let name = format!("{}{}", symbol.name, operator);
+        let list = Symbol { name: name.clone(), is_terminal: false, emptiness: Empty::NonEmpty };
+        let (kind, productions) = match operator {
+            '?' => (HelperKind::Optional, vec![vec![], vec![symbol]]),
+            '*' => (HelperKind::Repetition, vec![vec![], vec![list, symbol]]),
+            _ => (HelperKind::Repetition, vec![vec![symbol.clone()], vec![list, symbol]]),
+        };
+        return self.add_helper_rule(name, kind, productions);
+    }
+
+    // The name of a helper rule is the text of its operator or group, so it cannot be the name of
+    // a rule in the file, and the same text anywhere else in the grammar uses the same rule.
+    fn add_helper_rule(&mut self, name: String, kind: HelperKind, productions: Vec<Vec<Symbol>>) -> Result<Symbol, FileParserError>
+    {
+        let symbol = Symbol { name: name.clone(), is_terminal: false, emptiness: Empty::NonEmpty };
+        if self.symbols.contains_key(&name) {
+            return Ok(symbol);
+        }
+
+        let productions = productions.into_iter().map(|prod| Box::into_raw(Box::new(Production { prod, action: None, id: 0, precedence: None }))).collect();
+        let rule = GrammarRule { name: name.clone(), productions, helper: Some(kind) };
+        if self.does_rule_contain_duplicate_prods(&rule) {
+            return Err(FileParserError::new(FileParserErrorKind::DuplicateProduction, None));
+        }
+        self.symbols.insert(name, false);
+        self.helper_rules.push(rule);
+        return Ok(symbol);
+    }
+
+    fn parse_production_action(line: &Vec<char>, index: &mut usize) -> Option<String>
+    {
+        // Skip whitespace
+        while *index < line.len() && char::is_whitespace(line[*index]) {
+            *index += 1;
         }
-        return Ok(Box::into_raw(Box::new(Production {prod: production})));
+
+        if *index >= line.len() || line[*index] != '{' {
+            return None;
+        }
+
+        // The action code spans the rest of the line
+        let code: String = line[*index..].iter().collect();
+        *index = line.len();
+        return Some(code.trim().to_string());
+    }
+
+    fn parse_string_literal(line: &Vec<char>, index: &mut usize) -> Result<Option<String>, FileParserError>
+    {
+        // Skip whitespace
+        while *index < line.len() && char::is_whitespace(line[*index]) {
+            *index += 1;
+        }
+
+        if *index >= line.len() || line[*index] != '"' {
+            return Ok(None);
+        }
+        *index += 1;
+
+        let mut literal = String::new();
+        let mut escaped = false;
+        while *index < line.len() {
+            let c = line[*index];
+            *index += 1;
+            if escaped {
+                literal.push(match c {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    _ => c,
+                });
+                escaped = false;
+            }
+            else if c == '\\' {
+                escaped = true;
+            }
+            else if c == '"' {
+                if literal.is_empty() {
+                    return Err(FileParserError::new(FileParserErrorKind::InvalidStringLiteral, None));
+                }
+                return Ok(Some(literal));
+            }
+            else {
+                literal.push(c);
+            }
+        }
+
+        // The closing quote is missing
+        return Err(FileParserError::new(FileParserErrorKind::InvalidStringLiteral, None));
+    }
+
+    // Adds the terminal for a string literal if it does not exist yet and returns its name
+    fn add_literal(&mut self, literal: String) -> String
+    {
+        let name = FileParser::get_literal_name(&literal);
+        if !self.symbols.contains_key(&name) {
+            self.symbols.insert(name.clone(), true);
+            self.literals.push(literal);
+        }
+        return name;
+    }
+
+    // The name of the terminal for a string literal is the literal in quotes
+    fn get_literal_name(literal: &str) -> String
+    {
+        return format!("{:?}", literal);
     }
 
     fn parse_grammar_rule_end(&self, line: &Vec<char>) -> Result<bool, FileParserError>
@@ -569,39 +1048,6 @@ impl FileParser {
         return Some(identifier);
     }
 
-    fn does_rule_contain_unknown(&self, rule: &GrammarRule) -> bool
-    {
-        for prod in &rule.productions {
-            unsafe {
-                for sym in &(**prod).prod {
-                    if !self.symbols.contains_key(&sym.name) {
-                        return true;
-                    }
-                }
-            }
-        }
-        return false;
-    }
-
-    fn does_rule_contain_valid_identifiers(&self, rule: &GrammarRule) -> bool
-    {
-        if !FileParser::is_identifier_valid(&rule.name)
-        {
-            return false;
-        } 
-
-        for prod in &rule.productions {
-            unsafe {
-                for sym in &(**prod).prod {
-                    if !FileParser::is_identifier_valid(&sym.name) {
-                        return false;
-                    }
-                }
-            }
-        }
-        return true;
-    }
-
     fn is_identifier_valid(identifier: &str) -> bool
     {
         if identifier == "eof" {
@@ -624,7 +1070,7 @@ impl FileParser {
             for j in i+1..rule.productions.len()
             {
                 unsafe {
-                    if *rule.productions[i] == *rule.productions[j]
+                    if (*rule.productions[i]).prod == (*rule.productions[j]).prod
                     {
                         return true;
                     }
@@ -666,6 +1112,18 @@ impl FileParser {
         return Ok(nfa);
     }
 
+    // Maps the priority of each lexer rule with action code to its code
+    pub fn get_lexer_actions(&self) -> HashMap<i32, String>
+    {
+        let mut actions = HashMap::new();
+        for rule in &self.rules {
+            if let Some(action) = &rule.action {
+                actions.insert(rule.priority, action.to_string());
+            }
+        }
+        return actions;
+    }
+
     pub fn get_emptiness_or_default(&self, name: &str) -> Empty
     {
         if let Some(value) = self.emptiness_info.get(name)
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::code_gen::CodeGen;
+use crate::dfa_builder::DFABuilder;
+use crate::file_parser::{FileParser, FileParserError};
+use crate::grammar2::{Action, Conflict, Empty, GrammarGenerator, Symbol, TableKind};
+use crate::table_dfa_builder::{LexerMode, TableDFA, TableDFABuilder, TransitionStyle};
+
+/// A spec built in code instead of read from a file.
+///
+/// Each method adds a line in the file format, so the rules are validated
+/// the same way as the rules of a spec file.
+#[derive(Debug, Clone, Default)]
+pub struct Spec {
+    code: Option<String>,
+    lexer_rules: Vec<String>,
+    grammar_lines: Vec<String>,
+}
+
+impl Spec {
+    pub fn new() -> Spec
+    {
+        return Spec::default();
+    }
+
+    /// Sets the code that is copied to the top of the generated source.
+    pub fn code(mut self, code: &str) -> Spec
+    {
+        self.code = Some(code.to_string());
+        return self;
+    }
+
+    /// Adds a named lexer rule. Spaces in the regex must be escaped with `\`.
+    pub fn lexer_rule(mut self, name: &str, regex: &str) -> Spec
+    {
+        self.lexer_rules.push(format!("{} {}", name, regex));
+        return self;
+    }
+
+    /// Adds a named lexer rule with action code in curly braces.
+    pub fn lexer_rule_with_action(mut self, name: &str, regex: &str, action: &str) -> Spec
+    {
+        self.lexer_rules.push(format!("{} {} {}", name, regex, action));
+        return self;
+    }
+
+    /// Adds a lexer rule whose matches are skipped.
+    pub fn unnamed_rule(self, regex: &str) -> Spec
+    {
+        return self.lexer_rule("unnamed", regex);
+    }
+
+    /// Adds a `%type`, `%left`, `%right`, `%nonassoc` or `%ast` declaration.
+    pub fn declaration(mut self, declaration: &str) -> Spec
+    {
+        self.grammar_lines.push(declaration.to_string());
+        return self;
+    }
+
+    /// Adds a grammar rule. Each production is written as in the file format,
+    /// e.g. `expression "+" term { $$ = $1 + $3; }`.
+    pub fn grammar_rule(mut self, name: &str, productions: &[&str]) -> Spec
+    {
+        let first = productions.first().copied().unwrap_or("");
+        self.grammar_lines.push(format!("{}: {}", name, first));
+        for production in productions.iter().skip(1) {
+            self.grammar_lines.push(format!("| {}", production));
+        }
+        self.grammar_lines.push(";".to_string());
+        return self;
+    }
+}
+
+impl Display for Spec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(code) = &self.code {
+            writeln!(f, "SECTION CODE")?;
+            writeln!(f, "{}", code)?;
+        }
+        writeln!(f, "SECTION LEXER")?;
+        for rule in &self.lexer_rules {
+            writeln!(f, "{}", rule)?;
+        }
+        writeln!(f, "SECTION GRAMMAR")?;
+        for line in &self.grammar_lines {
+            writeln!(f, "{}", line)?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    // The spec could not be read or is invalid
+    Spec(FileParserError),
+    // A regex of the lexer section is invalid
+    Lexer(Box<dyn Error>),
+    // The parse table has conflicts that are not resolved by precedence
+    Conflicts(Vec<Conflict>),
+    // The generated source could not be written
+    Output(std::io::Error),
+}
+
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            Self::Spec(error) => write!(f, "{}", error),
+            Self::Lexer(error) => write!(f, "{}", error),
+            Self::Conflicts(conflicts) => {
+                write!(f, "The grammar has {} unresolved conflicts", conflicts.iter().filter(|conflict| !conflict.is_resolved()).count())?;
+                for conflict in conflicts.iter().filter(|conflict| !conflict.is_resolved()) {
+                    write!(f, "\n{}", conflict)?;
+                }
+                Ok(())
+            },
+            Self::Output(error) => write!(f, "{}", error),
+        };
+    }
+}
+
+impl Error for GeneratorError {}
+
+enum Source {
+    File(String),
+    Text(String),
+}
+
+/// Runs the pipeline from a spec to the lexer table, the parse tables and
+/// the generated source.
+pub struct Generator {
+    source: Source,
+    table_kind: TableKind,
+    lexer_mode: LexerMode,
+    transition_style: TransitionStyle,
+}
+
+impl Generator {
+    /// Reads the spec from the file at the path.
+    pub fn from_file(path: &str) -> Generator
+    {
+        return Generator { source: Source::File(path.to_string()), table_kind: TableKind::LR1, lexer_mode: LexerMode::Chars, transition_style: TransitionStyle::Table };
+    }
+
+    /// Reads the spec from text in the file format, e.g. from `include_str!`.
+    pub fn from_text(spec: &str) -> Generator
+    {
+        return Generator { source: Source::Text(spec.to_string()), table_kind: TableKind::LR1, lexer_mode: LexerMode::Chars, transition_style: TransitionStyle::Table };
+    }
+
+    pub fn from_spec(spec: &Spec) -> Generator
+    {
+        return Generator::from_text(&spec.to_string());
+    }
+
+    /// Sets the kind of parse table, which is LR(1) by default.
+    pub fn table(mut self, kind: TableKind) -> Generator
+    {
+        self.table_kind = kind;
+        return self;
+    }
+
+    /// Sets whether the generated lexer reads characters or UTF-8 bytes, which is characters by default.
+    pub fn lexer_mode(mut self, mode: LexerMode) -> Generator
+    {
+        self.lexer_mode = mode;
+        return self;
+    }
+
+    /// Sets how the generated lexer finds its transitions, which is with tables by default.
+    pub fn transition_style(mut self, style: TransitionStyle) -> Generator
+    {
+        self.transition_style = style;
+        return self;
+    }
+
+    pub fn generate(&self) -> Result<Generated, GeneratorError>
+    {
+        let mut file_parser = FileParser::new();
+        let result = match &self.source {
+            Source::File(path) => file_parser.parse_file(path),
+            Source::Text(text) => file_parser.parse_str(text),
+        };
+        result.map_err(GeneratorError::Spec)?;
+
+        // The raw pointers of the NFA, DFA and LR items never leave this function
+        unsafe {
+            let nfa = file_parser.build_nfa().map_err(GeneratorError::Lexer)?;
+            let dfa = DFABuilder::convert_nfa_to_dfa(nfa);
+
+            let mut table_builder = TableDFABuilder {
+                mapping: HashMap::new(),
+                node_counter: 1,
+            };
+            // Equivalent states are merged so that characters with the same transitions share a class
+            let table = table_builder.build_table_dfa(dfa).minimize();
+
+            let lexer_actions = file_parser.get_lexer_actions();
+            let value_types = file_parser.value_types.clone();
+            let code = file_parser.code.clone();
+            let ast = file_parser.ast;
+
+            let mut grammar_gen = GrammarGenerator::new(file_parser.get_terminals());
+            grammar_gen.precedences = file_parser.precedences.clone();
+            for rule in file_parser.grammar_rules {
+                let symbol = Symbol::new(rule.name.to_string(), false, Empty::NonEmpty);
+                grammar_gen.add_rule(symbol, rule);
+            }
+            let cc = grammar_gen.build_collection(self.table_kind);
+            grammar_gen.build_table(&cc);
+
+            // Conflicts resolved by precedence are kept as warnings
+            if grammar_gen.conflicts.iter().any(|conflict| !conflict.is_resolved()) {
+                return Err(GeneratorError::Conflicts(grammar_gen.conflicts));
+            }
+
+            let code_gen = CodeGen {
+                table,
+                lexer_mode: self.lexer_mode,
+                transition_style: self.transition_style,
+                curr_state_name: "curr".to_string(),
+                grammar_gen,
+                lexer_actions,
+                value_types,
+                ast,
+                code,
+            };
+            return Ok(Generated { code_gen });
+        }
+    }
+}
+
+/// The lexer table, the parse tables and the source generated from a spec.
+pub struct Generated {
+    code_gen: CodeGen,
+}
+
+impl Generated {
+    pub fn lexer_table(&self) -> &TableDFA
+    {
+        return &self.code_gen.table;
+    }
+
+    pub fn action_table(&self) -> &HashMap<(usize, Symbol), Action>
+    {
+        return &self.code_gen.grammar_gen.action_table;
+    }
+
+    pub fn goto_table(&self) -> &HashMap<(usize, Symbol), usize>
+    {
+        return &self.code_gen.grammar_gen.goto_table;
+    }
+
+    /// The conflicts that were resolved by the precedence declarations.
+    pub fn conflicts(&self) -> &Vec<Conflict>
+    {
+        return &self.code_gen.grammar_gen.conflicts;
+    }
+
+    /// The source of the generated lexer and parser.
+    pub fn source(&self) -> String
+    {
+        return self.code_gen.generate_source();
+    }
+
+    /// The source of the generated lexer and parser without a `main` function,
+    /// so that it can be embedded in another crate.
+    pub fn module_source(&self) -> String
+    {
+        return self.code_gen.generate_module();
+    }
+
+    pub fn write_to_file(&self, path: &str) -> std::io::Result<()>
+    {
+        return self.code_gen.generate_lexer(path);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::{HashMap, HashSet, BTreeSet}, fmt::Display};
+use std::{collections::{HashMap, HashSet, BTreeMap, BTreeSet}, fmt::Display, str::FromStr};
 
 #[derive(Eq, Hash, PartialEq, Clone, Debug, PartialOrd, Ord)]
 pub enum Empty {
@@ -16,6 +16,9 @@ pub struct Symbol {
 }
 
 impl Symbol {
+    // The name of the terminal used by productions that recover from syntax errors
+    pub const ERROR_NAME: &'static str = "error";
+
     pub fn new(name: String, is_terminal: bool, emptiness: Empty) -> Symbol
     {
         Symbol { name, is_terminal, emptiness }
@@ -27,16 +30,127 @@ impl Symbol {
     }
 }
 
+// The kind of a rule that the file parser adds for an operator or a group in a production
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelperKind {
+    // x? is a rule with an empty production and a production of x
+    Optional,
+    // x* and x+ are left recursive rules whose nested nodes are flattened into a list
+    Repetition,
+    // (...) has a production for each alternative
+    Group,
+}
+
 #[derive(Debug, Clone)]
 pub struct GrammarRule {
     pub name: String,
     pub productions: Vec<*mut Production>,
+    // None for the rules of the input file
+    pub helper: Option<HelperKind>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Production {
     //pub lhs: Symbol,
     pub prod: Vec<Symbol>,
+    // Code in curly braces that runs when the production is reduced
+    pub action: Option<String>,
+    // Index of the production in GrammarGenerator::productions
+    pub id: usize,
+    // Name given with %prec whose precedence is used for the production
+    pub precedence: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+    NonAssoc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Precedence {
+    // Declarations that appear later have a higher level
+    pub level: usize,
+    pub associativity: Associativity,
+}
+
+// The kind of parse table to build
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableKind {
+    LR1,
+    // LR(1) states with the same core are merged
+    LALR1,
+    // LR(0) states that reduce on the FOLLOW set of the rule
+    SLR1,
+}
+
+impl FromStr for TableKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_lowercase().as_str() {
+            "lr1" => Ok(TableKind::LR1),
+            "lalr1" => Ok(TableKind::LALR1),
+            "slr1" => Ok(TableKind::SLR1),
+            _ => Err(format!("'{}' is not a table kind. Use lr1, lalr1 or slr1.", s)),
+        };
+    }
+}
+
+// How a shift/reduce conflict is resolved by the precedence declarations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Shift,
+    Reduce,
+    // The lookahead is a syntax error because of %nonassoc
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    ShiftReduce,
+    ReduceReduce,
+}
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub state: usize,
+    pub lookahead: Symbol,
+    // The items that shift the lookahead or reduce on it, which are printed with the conflict
+    pub(crate) items: Vec<LRItem>,
+    // Only shift/reduce conflicts can be resolved by the precedence declarations
+    pub resolution: Option<Resolution>,
+    // True if none of the LR(1) states merged into the state has the conflict
+    pub introduced_by_merge: bool,
+}
+
+impl Conflict {
+    pub fn is_resolved(&self) -> bool
+    {
+        return self.resolution.is_some();
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            ConflictKind::ShiftReduce => "Shift/reduce",
+            ConflictKind::ReduceReduce => "Reduce/reduce",
+        };
+        write!(f, "{} conflict in state {} on lookahead {}", kind, self.state, self.lookahead.name)?;
+        match self.resolution {
+            Some(Resolution::Shift) => write!(f, " (resolved as shift by precedence)")?,
+            Some(Resolution::Reduce) => write!(f, " (resolved as reduce by precedence)")?,
+            Some(Resolution::Error) => write!(f, " (resolved as an error by %nonassoc)")?,
+            None => (),
+        }
+        for item in &self.items {
+            write!(f, "\n    {}", item)?;
+        }
+        return Ok(());
+    }
 }
 
 #[derive(Eq, Hash, PartialEq, Clone, Debug, PartialOrd, Ord)]
@@ -54,16 +168,7 @@ impl LRItem {
             return None;
         }
 
-        return Some((*self.production).prod[self.placeholder_index].clone());
-    }
-
-    unsafe fn get_symbol_after_next_symbol(&self) -> Symbol {
-        if self.placeholder_index + 1 >= (*self.production).prod.len()
-        {
-            return self.lookup_sym.clone();
-        }
-
-        return (*self.production).prod[self.placeholder_index+1].clone();
+        return Some((&(*self.production).prod)[self.placeholder_index].clone());
     }
 
     unsafe fn is_next_symbol(&self, symbol: &Symbol) -> bool
@@ -103,7 +208,7 @@ impl Display for LRItem {
                     result.push_str(".");
                 }
 
-                result.push_str(&(*self.production).prod[i].name);
+                result.push_str(&(&(*self.production).prod)[i].name);
 
                 if i != (*self.production).prod.len() - 1 {
                     result.push(' ');
@@ -141,9 +246,10 @@ pub struct GrammarSetInfo {
 #[derive(Debug)]
 pub enum Action {
     Shift(usize),
-    // LHS and length of production
-    Reduce(Symbol, usize),
-    Accept
+    // LHS, length of production and production id
+    Reduce(Symbol, usize, usize),
+    // The input is a complete root
+    Accept,
 }
 
 pub struct GrammarGenerator {
@@ -152,7 +258,25 @@ pub struct GrammarGenerator {
     pub action_table: HashMap<(usize, Symbol), Action>,
     pub goto_table: HashMap<(usize, Symbol), usize>,
     pub non_terminals: HashSet<Symbol>,
+    // The terminals of the lexer, kept next to the non-terminals of the grammar
+    #[allow(dead_code)]
     pub terminals: HashSet<Symbol>,
+    pub productions: Vec<*mut Production>,
+    // Maps terminal names to their declared precedence
+    pub precedences: HashMap<String, Precedence>,
+    // Conflicts found by build_table
+    pub conflicts: Vec<Conflict>,
+    // Maps each LALR(1) state to the LR(1) states that were merged into it, which are
+    // only built when the LALR(1) table has conflicts
+    merged_states: HashMap<usize, Vec<GrammarSet>>,
+    // Maps the items without lookaheads of each LALR(1) state to its id
+    lalr_cores: HashMap<BTreeSet<(*const Production, usize)>, usize>,
+    // The production root' -> root, which is completed when the whole input is parsed
+    augmented_production: *mut Production,
+    // Names of the rules that can derive the empty string
+    nullable: HashSet<String>,
+    // Maps the name of each rule to the terminals that can begin it
+    first_sets: HashMap<String, HashSet<Symbol>>,
 }
 
 impl GrammarGenerator {
@@ -166,97 +290,110 @@ impl GrammarGenerator {
             goto_table: HashMap::new(),
             non_terminals: HashSet::new(),
             terminals: terminals,
+            productions: Vec::new(),
+            precedences: HashMap::new(),
+            conflicts: Vec::new(),
+            merged_states: HashMap::new(),
+            lalr_cores: HashMap::new(),
+            augmented_production: Box::into_raw(Box::new(Production {
+                prod: vec![Symbol::new("root".to_string(), false, Empty::NonEmpty)],
+                action: None,
+                id: usize::MAX,
+                precedence: None,
+            })),
+            nullable: HashSet::new(),
+            first_sets: HashMap::new(),
         }
     }
 
     pub fn add_rule(&mut self, symbol: Symbol, rule: GrammarRule)
     {
+        // Give each production an id so that the generated parser can find its action
+        for prod in &rule.productions {
+            unsafe {
+                (**prod).id = self.productions.len();
+            }
+            self.productions.push(*prod);
+        }
         self.non_terminals.insert(symbol.clone());
         self.rules.insert(symbol, rule);
     }
 
-    pub unsafe fn get_first_set_lr_item(&self, item: *const LRItem) -> HashSet<Symbol>
+    pub fn get_rule(&self, symbol: &Symbol) -> Option<&GrammarRule>
     {
-        let mut result: HashSet<Symbol> = HashSet::new();
-        let mut index = (*item).placeholder_index + 1;
-        let size = (*(*item).production).prod.len();
-        let mut checked_lookup = false;
-
-        while index < size || !checked_lookup {
-            // Get symbol at index
-            let sym;
-            if index >= (*(*item).production).prod.len() {
-                sym = &(*item).lookup_sym;
-                checked_lookup = true;
-            } else {
-                sym = &(*(*item).production).prod[index];
-            }
+        return self.rules.get(symbol);
+    }
 
-            //println!("{:#?}", sym);
+    // Computes which rules can derive the empty string and the first set of every rule.
+    // Terminals are never empty since the lexer does not produce empty tokens.
+    pub fn compute_first_sets(&mut self)
+    {
+        self.nullable.clear();
+        self.first_sets.clear();
+        for symbol in self.rules.keys() {
+            self.first_sets.insert(symbol.name.to_string(), HashSet::new());
+        }
 
-            match sym.emptiness {
-                Empty::NonEmpty => {
-                    let temp = self.get_first_set(sym);
-                    for temp_sym in temp {
-                        result.insert(temp_sym);
+        // Repeat until neither the nullable rules nor the first sets change
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (symbol, rule) in &self.rules {
+                for prod in &rule.productions {
+                    let prod = unsafe { &(**prod).prod };
+                    let (first_set, is_nullable) = self.get_first_set_of_sequence(prod);
+
+                    if is_nullable && self.nullable.insert(symbol.name.to_string()) {
+                        changed = true;
                     }
-                    return result;
-                },
-                Empty::Empty => {
-                    // Skip current symbol
-                    index += 1;
-                },
-                Empty::PossiblyEmpty => {
-                    println!("here1");
-                    let temp = self.get_first_set(sym);
-                    for temp_sym in temp {
-                        result.insert(temp_sym);
+
+                    let rule_first_set = self.first_sets.get_mut(&symbol.name).unwrap();
+                    for terminal in first_set {
+                        changed |= rule_first_set.insert(terminal);
                     }
-                    index += 1;
-                },
+                }
             }
         }
-        return result;
     }
 
-    // Function to compute first set
-    pub fn get_first_set(&self, symbol: &Symbol) -> HashSet<Symbol>
+    pub fn is_nullable(&self, symbol: &Symbol) -> bool
     {
-        // Clone string to create the stack
-        let mut stack: Vec<Symbol> = Vec::new();
-        stack.push(symbol.clone());
-        let mut seen: HashSet<Symbol> = HashSet::new();
-
-        // Set of symbols in first set
-        let mut set: HashSet<Symbol> = HashSet::new();
-
-        while !stack.is_empty() {
-            let front = stack.remove(0);
+        return !symbol.is_terminal && self.nullable.contains(&symbol.name);
+    }
 
-            if !seen.insert(front.clone()) {
-                continue;
+    // Returns the first set of the sequence of symbols and whether the whole sequence can be empty
+    pub fn get_first_set_of_sequence(&self, symbols: &[Symbol]) -> (HashSet<Symbol>, bool)
+    {
+        let mut result: HashSet<Symbol> = HashSet::new();
+        for sym in symbols {
+            result.extend(self.get_first_set(sym));
+            if !self.is_nullable(sym) {
+                return (result, false);
             }
+        }
+        return (result, true);
+    }
 
-            if front.is_terminal {
-                set.insert(front.clone());
-            }
-            else {
-                if let Some(rule) = self.rules.get(&front) {
-                    // Run get first set on the productions
-                    for prod in &rule.productions {
-                        unsafe {
-                            let prod_first_sym = &(**prod).prod[0];
-                            stack.push(prod_first_sym.clone());
-                        }
-                    }
-                }
-                else {
-                    // Throw an error here
-                    todo!();
-                }
-            }
+    // The lookaheads of the items added to the closure for the symbol after the placeholder
+    pub unsafe fn get_first_set_lr_item(&self, item: *const LRItem) -> HashSet<Symbol>
+    {
+        let prod = &(*(*item).production).prod;
+        let (mut result, is_nullable) = self.get_first_set_of_sequence(&prod[(*item).placeholder_index + 1..]);
+        if is_nullable {
+            result.insert((*item).lookup_sym.clone());
         }
-        return set;
+        return result;
+    }
+
+    pub fn get_first_set(&self, symbol: &Symbol) -> HashSet<Symbol>
+    {
+        if symbol.is_terminal {
+            return HashSet::from([symbol.clone()]);
+        }
+        return match self.first_sets.get(&symbol.name) {
+            Some(value) => value.clone(),
+            None => HashSet::new(),
+        };
     }
 
     pub fn get_closure(&mut self, set: GrammarSet) -> GrammarSet
@@ -295,17 +432,9 @@ impl GrammarGenerator {
                     continue;
                 }
 
-                // Get the first set for this symbol
-                let sym_after_next_sym = (*lr_item).get_symbol_after_next_symbol();
-                let first_set1 = self.get_first_set(&sym_after_next_sym);
-
+                // Get the lookaheads for the items of the next symbol
                 let first_set = self.get_first_set_lr_item(lr_item);
 
-                println!("Sym: {:?}, Set: {:#?}", sym_after_next_sym, first_set);
-                println!("Set 1: {:#?}", first_set1);
-
-                //println!("sym: {}, first set: {:?}", next_sym.name, first_set);
-
                 let rule_for_next = match self.rules.get(&next_sym) {
                     Some(value) => value,
                     None => todo!(),
@@ -346,6 +475,8 @@ impl GrammarGenerator {
 
     pub fn build_cannocial_collection(&mut self) -> HashMap<GrammarSet, GrammarSetInfo>
     {
+        self.compute_first_sets();
+
         let mut sets: HashMap<GrammarSet, GrammarSetInfo> = HashMap::new();
         let cc_0 = self.get_goal_grammar_set();
         let cc_0 = self.get_closure(cc_0);
@@ -387,24 +518,290 @@ impl GrammarGenerator {
         return sets;
     }
 
+    // Builds the collection of states for the kind of table. LALR(1) and SLR(1) use the states of the
+    // LR(0) automaton, so they never build the much larger canonical LR(1) collection.
+    pub unsafe fn build_collection(&mut self, kind: TableKind) -> HashMap<GrammarSet, GrammarSetInfo>
+    {
+        return match kind {
+            TableKind::LR1 => self.build_cannocial_collection(),
+            TableKind::LALR1 => {
+                let cc = self.build_lalr_collection();
+                self.lalr_cores = cc.iter().map(|(set, info)| (GrammarGenerator::get_core(set), info.id)).collect();
+                cc
+            },
+            TableKind::SLR1 => {
+                let cc = self.build_lalr_collection();
+                self.use_follow_lookaheads(cc)
+            },
+        };
+    }
+
+    // The items of the set without their lookaheads
+    unsafe fn get_core(set: &GrammarSet) -> BTreeSet<(*const Production, usize)>
+    {
+        return set.set.iter().map(|item| ((**item).production, (**item).placeholder_index)).collect();
+    }
+
+    // Builds the LR(0) automaton, where an item is a production and the index of the placeholder.
+    // Returns the kernel items of each state, which are the items that the closure starts from,
+    // and the transitions of each state.
+    unsafe fn build_lr0_automaton(&self) -> (Vec<BTreeSet<(*const Production, usize)>>, Vec<HashMap<Symbol, usize>>)
+    {
+        let start = BTreeSet::from([(self.augmented_production as *const Production, 0)]);
+        let mut kernels = vec![start.clone()];
+        let mut transitions: Vec<HashMap<Symbol, usize>> = vec![HashMap::new()];
+        let mut ids: HashMap<BTreeSet<(*const Production, usize)>, usize> = HashMap::from([(start, 0)]);
+
+        let mut index = 0;
+        while index < kernels.len() {
+            // Group the items of the closure by the symbol after the placeholder
+            let mut gotos: BTreeMap<Symbol, BTreeSet<(*const Production, usize)>> = BTreeMap::new();
+            for (production, placeholder) in self.get_lr0_closure(&kernels[index]) {
+                if let Some(next_sym) = (&(*production).prod).get(placeholder) {
+                    gotos.entry(next_sym.clone()).or_default().insert((production, placeholder + 1));
+                }
+            }
+
+            for (sym, kernel) in gotos {
+                let id = match ids.get(&kernel) {
+                    Some(id) => *id,
+                    None => {
+                        ids.insert(kernel.clone(), kernels.len());
+                        kernels.push(kernel);
+                        transitions.push(HashMap::new());
+                        kernels.len() - 1
+                    },
+                };
+                transitions[index].insert(sym, id);
+            }
+            index += 1;
+        }
+        return (kernels, transitions);
+    }
+
+    unsafe fn get_lr0_closure(&self, kernel: &BTreeSet<(*const Production, usize)>) -> BTreeSet<(*const Production, usize)>
+    {
+        let mut result = kernel.clone();
+        let mut stack: Vec<(*const Production, usize)> = kernel.iter().cloned().collect();
+        while let Some((production, placeholder)) = stack.pop() {
+            let next_sym = match (&(*production).prod).get(placeholder) {
+                Some(value) if !value.is_terminal => value,
+                _ => continue,
+            };
+            if let Some(rule) = self.rules.get(next_sym) {
+                for prod in &rule.productions {
+                    if result.insert((*prod as *const Production, 0)) {
+                        stack.push((*prod, 0));
+                    }
+                }
+            }
+        }
+        return result;
+    }
+
+    // Builds the LALR(1) states from the LR(0) automaton by finding the lookaheads of the kernel items.
+    // The closure of each kernel item with a placeholder lookahead shows which lookaheads the item
+    // generates for the kernel items of the next states and which it passes on to them, and the
+    // lookaheads are passed on until they no longer change.
+    unsafe fn build_lalr_collection(&mut self) -> HashMap<GrammarSet, GrammarSetInfo>
+    {
+        self.compute_first_sets();
+        let (kernels, transitions) = self.build_lr0_automaton();
+
+        let lhs: HashMap<*const Production, Symbol> = self.rules.iter()
+            .flat_map(|(symbol, rule)| rule.productions.iter().map(move |prod| (*prod as *const Production, symbol.clone())))
+            .chain([(self.augmented_production as *const Production, Symbol::new("root'".to_string(), false, Empty::NonEmpty))])
+            .collect();
+        // The placeholder lookahead is not a name that a terminal can have
+        let placeholder = Symbol::new("#".to_string(), true, Empty::NonEmpty);
+
+        let mut lookaheads: HashMap<(usize, *const Production, usize), HashSet<Symbol>> = HashMap::new();
+        let mut passes: Vec<((usize, *const Production, usize), (usize, *const Production, usize))> = Vec::new();
+        lookaheads.entry((0, self.augmented_production, 0)).or_default().insert(Symbol::eof_symbol());
+
+        for (state, kernel) in kernels.iter().enumerate() {
+            for (production, index) in kernel {
+                let item = self.add_lr_item_or_get_existing(LRItem { production: *production, placeholder_index: *index, lookup_sym: placeholder.clone(), lhs: lhs[production].clone() });
+                let closure = self.get_closure(GrammarSet::new(BTreeSet::from([item])));
+                for closure_item in &closure.set {
+                    let next_sym = match (**closure_item).get_next_symbol() {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    let target = (transitions[state][&next_sym], (**closure_item).production, (**closure_item).placeholder_index + 1);
+                    if (**closure_item).lookup_sym == placeholder {
+                        passes.push(((state, *production, *index), target));
+                    }
+                    else {
+                        lookaheads.entry(target).or_default().insert((**closure_item).lookup_sym.clone());
+                    }
+                }
+            }
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (from, to) in &passes {
+                let to_add: Vec<Symbol> = match lookaheads.get(from) {
+                    Some(value) => value.iter().cloned().collect(),
+                    None => continue,
+                };
+                let target = lookaheads.entry(*to).or_default();
+                for sym in to_add {
+                    changed |= target.insert(sym);
+                }
+            }
+        }
+
+        // Each state is the closure of its kernel items with their lookaheads
+        let mut result = HashMap::new();
+        for (state, kernel) in kernels.iter().enumerate() {
+            let mut set = GrammarSet::new(BTreeSet::new());
+            for (production, index) in kernel {
+                for sym in lookaheads.get(&(state, *production, *index)).into_iter().flatten() {
+                    set.set.insert(self.add_lr_item_or_get_existing(LRItem { production: *production, placeholder_index: *index, lookup_sym: sym.clone(), lhs: lhs[production].clone() }));
+                }
+            }
+            let set = self.get_closure(set);
+            result.insert(set, GrammarSetInfo { id: state, transitions: transitions[state].clone() });
+        }
+        return result;
+    }
+
+    // Finds the LR(1) states that have the same core as each LALR(1) state
+    unsafe fn find_merged_states(&mut self)
+    {
+        let cc = self.build_cannocial_collection();
+        let mut merged_states: HashMap<usize, Vec<GrammarSet>> = HashMap::new();
+        for set in cc.into_keys() {
+            if let Some(id) = self.lalr_cores.get(&GrammarGenerator::get_core(&set)) {
+                merged_states.entry(*id).or_default().push(set);
+            }
+        }
+        self.merged_states = merged_states;
+    }
+
+    // Replaces the lookaheads of the completed items with the FOLLOW set of their rule
+    unsafe fn use_follow_lookaheads(&mut self, cc: HashMap<GrammarSet, GrammarSetInfo>) -> HashMap<GrammarSet, GrammarSetInfo>
+    {
+        let follow_sets = self.get_follow_sets();
+        let mut result = HashMap::new();
+        for (set, info) in cc {
+            let mut new_set = GrammarSet::new(BTreeSet::new());
+            for item in &set.set {
+                if !(**item).is_lookup_at_end() || (**item).production == self.augmented_production {
+                    new_set.set.insert(*item);
+                    continue;
+                }
+                if let Some(follow_set) = follow_sets.get(&(**item).lhs.name) {
+                    for sym in follow_set {
+                        let new_item = LRItem {
+                            production: (**item).production,
+                            placeholder_index: (**item).placeholder_index,
+                            lookup_sym: sym.clone(),
+                            lhs: (**item).lhs.clone(),
+                        };
+                        new_set.set.insert(self.add_lr_item_or_get_existing(new_item));
+                    }
+                }
+            }
+            result.insert(new_set, info);
+        }
+        return result;
+    }
+
+    // Maps the name of each rule to the terminals that can follow it
+    pub fn get_follow_sets(&self) -> HashMap<String, HashSet<Symbol>>
+    {
+        let mut follow_sets: HashMap<String, HashSet<Symbol>> = HashMap::new();
+        follow_sets.entry("root".to_string()).or_default().insert(Symbol::eof_symbol());
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (symbol, rule) in &self.rules {
+                for prod in &rule.productions {
+                    let prod = unsafe { &(**prod).prod };
+                    for (index, sym) in prod.iter().enumerate() {
+                        if sym.is_terminal {
+                            continue;
+                        }
+
+                        // Add the first set of the rest of the production and,
+                        // if the rest can be empty, the follow set of the rule
+                        let (mut to_add, rest_can_be_empty) = self.get_first_set_of_sequence(&prod[index + 1..]);
+                        if rest_can_be_empty {
+                            if let Some(follow_set) = follow_sets.get(&symbol.name) {
+                                to_add.extend(follow_set.iter().cloned());
+                            }
+                        }
+
+                        let follow_set = follow_sets.entry(sym.name.to_string()).or_default();
+                        for terminal in to_add {
+                            changed |= follow_set.insert(terminal);
+                        }
+                    }
+                }
+            }
+        }
+        return follow_sets;
+    }
+
     pub unsafe fn build_table(&mut self, cc: &HashMap<GrammarSet, GrammarSetInfo>)
     {
         for (key, value) in cc {
 
-            // For each item in the set
+            // Group the items by the terminal they shift or reduce on
+            let mut shift_items: HashMap<Symbol, Vec<*mut LRItem>> = HashMap::new();
+            let mut reduce_items: HashMap<Symbol, Vec<*mut LRItem>> = HashMap::new();
             for item in &key.set {
-                
-                // Check for shift action
                 if let Some(next_sym) = (**item).get_next_symbol() {
-                    if let Some(reduce_dest) = value.transitions.get(&next_sym) {
-                        self.action_table.insert((value.id, next_sym.clone()), Action::Shift(*reduce_dest));
+                    if next_sym.is_terminal {
+                        shift_items.entry(next_sym).or_default().push(*item);
                     }
-                } 
-                else if (**item).lhs.name == "root" && (**item).lookup_sym.name == "eof" {
-                    self.action_table.insert((value.id, (**item).lookup_sym.clone()), Action::Accept);
                 }
                 else {
-                    self.action_table.insert((value.id, (**item).lookup_sym.clone()), Action::Reduce((**item).lhs.clone(), (*(**item).production).prod.len()));
+                    reduce_items.entry((**item).lookup_sym.clone()).or_default().push(*item);
+                }
+            }
+
+            let mut lookaheads: HashSet<Symbol> = shift_items.keys().cloned().collect();
+            lookaheads.extend(reduce_items.keys().cloned());
+
+            for lookahead in lookaheads {
+                let shift = shift_items.get(&lookahead).map(|_| Action::Shift(value.transitions[&lookahead]));
+
+                // Reduce/reduce conflicts are resolved by reducing the production that appears first
+                let mut reduces = reduce_items.get(&lookahead).cloned().unwrap_or_default();
+                reduces.sort_by_key(|item| (*(**item).production).id);
+                let reduce = reduces.first().map(|item| self.get_reduce_action(*item));
+
+                let action = match (shift, reduce) {
+                    (Some(shift), None) => Some(shift),
+                    (None, Some(reduce)) => Some(reduce),
+                    (Some(shift), Some(reduce)) => {
+                        // Shift/reduce conflicts are resolved by the precedence declarations or else by shifting.
+                        // The shift competes with the reduce that won the reduce/reduce conflict, if there is one.
+                        let resolution = self.resolve_shift_reduce(&lookahead, &reduce);
+                        let mut items = shift_items[&lookahead].clone();
+                        items.push(reduces[0]);
+                        self.add_conflict(ConflictKind::ShiftReduce, value.id, &lookahead, &items, resolution.clone());
+                        match resolution {
+                            Some(Resolution::Reduce) => Some(reduce),
+                            Some(Resolution::Error) => None,
+                            Some(Resolution::Shift) | None => Some(shift),
+                        }
+                    },
+                    (None, None) => None,
+                };
+
+                if reduces.len() > 1 {
+                    self.add_conflict(ConflictKind::ReduceReduce, value.id, &lookahead, &reduces, None);
+                }
+
+                if let Some(action) = action {
+                    self.action_table.insert((value.id, lookahead), action);
                 }
             }
 
@@ -415,6 +812,108 @@ impl GrammarGenerator {
                 }
             }
         }
+
+        // Only the conflicts of an LALR(1) table need the LR(1) states to tell if merging caused them
+        if !self.lalr_cores.is_empty() && !self.conflicts.is_empty() {
+            self.find_merged_states();
+            for index in 0..self.conflicts.len() {
+                let (state, lookahead) = (self.conflicts[index].state, self.conflicts[index].lookahead.clone());
+                self.conflicts[index].introduced_by_merge = self.is_conflict_introduced_by_merge(state, &lookahead);
+            }
+        }
+
+        self.conflicts.sort_by(|a, b| (a.state, &a.lookahead.name).cmp(&(b.state, &b.lookahead.name)));
+    }
+
+    unsafe fn get_reduce_action(&self, item: *const LRItem) -> Action
+    {
+        if (*item).production == self.augmented_production {
+            return Action::Accept;
+        }
+        return Action::Reduce((*item).lhs.clone(), (*(*item).production).prod.len(), (*(*item).production).id);
+    }
+
+    unsafe fn add_conflict(&mut self, kind: ConflictKind, state: usize, lookahead: &Symbol, items: &Vec<*mut LRItem>, resolution: Option<Resolution>)
+    {
+        let mut items: Vec<LRItem> = items.iter().map(|item| (**item).clone()).collect();
+        items.sort_by_key(|item| item.to_string());
+        let introduced_by_merge = self.is_conflict_introduced_by_merge(state, lookahead);
+        self.conflicts.push(Conflict { kind, state, lookahead: lookahead.clone(), items, resolution, introduced_by_merge });
+    }
+
+    unsafe fn is_conflict_introduced_by_merge(&self, state: usize, lookahead: &Symbol) -> bool
+    {
+        let originals = match self.merged_states.get(&state) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        for set in originals {
+            let mut shifts = false;
+            let mut reduces = HashSet::new();
+            for item in &set.set {
+                match (**item).get_next_symbol() {
+                    Some(next_sym) => shifts |= next_sym == *lookahead,
+                    None => {
+                        if (**item).lookup_sym == *lookahead {
+                            reduces.insert((*(**item).production).id);
+                        }
+                    },
+                }
+            }
+            if shifts as usize + reduces.len() > 1 {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // Returns None when the production or the lookahead does not have a precedence
+    unsafe fn resolve_shift_reduce(&self, lookahead: &Symbol, reduce: &Action) -> Option<Resolution>
+    {
+        let production = self.productions[GrammarGenerator::get_action_production_id(reduce)];
+        let (prod_prec, token_prec) = match (self.get_production_precedence(production), self.precedences.get(&lookahead.name)) {
+            (Some(prod_prec), Some(token_prec)) => (prod_prec, token_prec),
+            _ => return None,
+        };
+
+        if prod_prec.level > token_prec.level {
+            return Some(Resolution::Reduce);
+        }
+        else if prod_prec.level < token_prec.level {
+            return Some(Resolution::Shift);
+        }
+
+        return match token_prec.associativity {
+            Associativity::Left => Some(Resolution::Reduce),
+            Associativity::Right => Some(Resolution::Shift),
+            Associativity::NonAssoc => Some(Resolution::Error),
+        };
+    }
+
+    // The precedence of a production is given by %prec or else by its last terminal with a precedence
+    pub unsafe fn get_production_precedence(&self, production: *const Production) -> Option<Precedence>
+    {
+        if let Some(name) = &(*production).precedence {
+            return self.precedences.get(name).copied();
+        }
+
+        for sym in (&(*production).prod).iter().rev() {
+            if sym.is_terminal {
+                if let Some(precedence) = self.precedences.get(&sym.name) {
+                    return Some(*precedence);
+                }
+            }
+        }
+        return None;
+    }
+
+    fn get_action_production_id(action: &Action) -> usize
+    {
+        return match action {
+            Action::Reduce(_, _, id) => *id,
+            _ => panic!("Only a reduce action has a production"),
+        };
     }
 
     fn get_symbols_after_placeholder(&mut self, set: &GrammarSet) -> HashSet<Symbol>
@@ -455,25 +954,18 @@ impl GrammarGenerator {
     }
 
     pub fn get_goal_grammar_set(&mut self) -> GrammarSet {
-        let root_sym = Symbol { name: "root".to_string(), is_terminal: false, emptiness: crate::grammar2::Empty::NonEmpty };
-        let root_rule = match self.rules.get(&root_sym) {
-            Some(value) => value,
-            None => todo!(),
-        }.clone();
+        // The closure of root' -> .root, eof has the items of the root productions
+        let start_sym = Symbol::new("root'".to_string(), false, Empty::NonEmpty);
+        let lr_item = self.get_lr_item_from_prod(self.augmented_production, start_sym, Symbol::eof_symbol());
 
         let mut grammar_set = GrammarSet::new(BTreeSet::new());
-        let eof_sym = Symbol { name: "eof".to_string(), is_terminal: true, emptiness: crate::grammar2::Empty::NonEmpty };
-        
-        for prod in &root_rule.productions {
-            // Convert to LR Item
-            let lr_item = self.get_lr_item_from_prod(*prod, root_sym.clone(), eof_sym.clone());
-            grammar_set.set.insert(lr_item);
-        }
-
+        grammar_set.set.insert(lr_item);
         return grammar_set;
     }
     
 
+    // Runs the parse tables on a sequence of terminals, used to check the tables in the tests
+    #[cfg(test)]
     pub unsafe fn parse(&mut self, symbols: &Vec<Symbol>) -> bool {
         let mut stack: Vec<StackSymbol> = Vec::new();
         stack.push(StackSymbol::DollarSign);
@@ -494,7 +986,7 @@ impl GrammarGenerator {
             if let Some(action) = self.action_table.get(&key).clone() {
                 //println!("{:?}", action);
                 match action {
-                    Action::Reduce(lhs, prod_len) => {
+                    Action::Reduce(lhs, prod_len, _) => {
                         let num = 2 * prod_len;
                         for i in 0..num {
                             stack.pop();
@@ -548,6 +1040,7 @@ impl GrammarGenerator {
     }*/
 }
 
+#[cfg(test)]
 enum StackSymbol {
     Symbol(Symbol),
     State(usize),
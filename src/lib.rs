@@ -0,0 +1,58 @@
+//! RLPG generates a lexer and an LR parser from a single spec file.
+//!
+//! The [`Generator`] runs the whole pipeline and returns the lexer table,
+//! the parse tables and the generated Rust source. The spec can be read from
+//! a file, given as text, or built in code with a [`Spec`]:
+//!
+//! ```
+//! use rlpg::{Generator, Spec, TableKind};
+//!
+//! let spec = Spec::new()
+//!     .lexer_rule("number", "[0-9]+")
+//!     .grammar_rule("root", &["root \"+\" number", "number"]);
+//!
+//! let generated = Generator::from_spec(&spec).table(TableKind::LALR1).generate().unwrap();
+//! assert!(generated.source().contains("pub fn parse("));
+//! ```
+
+// The stages of the pipeline use raw pointers and change often, so only the
+// generator and the types it returns are public.
+mod file_parser;
+pub use crate::file_parser::{FileParserError, FileParserErrorKind};
+
+mod regex_parser;
+
+mod node_kind;
+
+mod nfa_builder;
+
+mod nfa;
+pub use crate::nfa::TransitionKind;
+
+#[cfg(test)]
+mod token;
+
+mod node;
+
+mod error;
+pub use crate::error::RlpgErr;
+
+mod tests;
+
+mod dfa_builder;
+
+#[cfg(test)]
+mod dfa_simulator;
+
+mod grammar2;
+pub use crate::grammar2::{Action, Conflict, ConflictKind, Empty, Resolution, Symbol, TableKind};
+
+mod table_dfa_builder;
+pub use crate::table_dfa_builder::{LexerMode, TableDFA, TransitionStyle};
+
+mod code_gen;
+
+mod generator;
+pub use crate::generator::{Generated, Generator, GeneratorError, Spec};
+
+pub mod build;
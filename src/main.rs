@@ -1,49 +1,9 @@
-mod file_parser;
-
-pub mod regex_parser;
-
-mod node_kind;
-
-mod nfa_builder;
-use std::collections::{HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::str::FromStr;
 
-use crate::code_gen::CodeGen;
-use crate::dfa_builder::DFABuilder;
-use crate::dfa_simulator::DFASimulator;
-use crate::error::RlpgErr;
-use crate::file_parser::FileParser;
-pub use crate::nfa_builder::NFABuilder;
-
-pub mod nfa;
-pub use crate::nfa::NFA;
-use crate::table_dfa_builder::TableDFABuilder;
-
-pub mod token;
-
 use clap::Parser;
-
-pub mod node;
-
-pub mod error;
-
-mod tests;
-
-mod dfa_builder;
-
-mod dfa_simulator;
-
 use colored::*;
-use dfa_builder::DFANode;
-use grammar2::{Symbol, Production, GrammarRule, GrammarGenerator};
-
-
-mod grammar2;
-
-mod table_dfa_builder;
-
-mod code_gen;
+use rlpg::{Conflict, Generator, GeneratorError, LexerMode, TableKind, TransitionStyle};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -54,118 +14,81 @@ struct Args {
     /// Path of the output file
     #[arg(long)]
     output: String,
+    /// Kind of parse table: lr1, lalr1 or slr1
+    #[arg(long, default_value = "lr1")]
+    table: TableKind,
+    /// Input of the generated lexer: chars or bytes
+    #[arg(long, default_value = "chars")]
+    lexer: LexerMode,
+    /// How the generated lexer finds its transitions: table or branches
+    #[arg(long, default_value = "table")]
+    transitions: TransitionStyle,
 }
 
-fn main() {
-
-    let args = Args::parse();
+// Prints the conflict and returns true if it is an error
+fn report_conflict(conflict: &Conflict) -> bool {
+    if conflict.is_resolved() {
+        println!("{}", format!("Warning: {}", conflict).yellow());
+        return false;
+    }
+    println!("{}", format!("Error: {}", conflict).red());
+    return true;
+}
 
-    let mut file_parser = FileParser::new();
+// Prints the conflicts and returns true if any of them is an error
+fn report_conflicts(conflicts: &Vec<Conflict>) -> bool {
+    let mut has_errors = false;
 
-    // Open the file and parse it
-    let file_parse_result = file_parser.parse_file(&args.filename);
-    if let Err(error) = file_parse_result
-    {
-        println!("{}", format!("Error: {}", error.to_string()).red());
-        return;
+    // Conflicts resolved by precedence are warnings, all others are errors
+    for conflict in conflicts.iter().filter(|conflict| !conflict.introduced_by_merge) {
+        has_errors |= report_conflict(conflict);
     }
-    //let rules = file_parse_result.unwrap();
-
 
-    // Take the rules and build an NFA
-    unsafe {
-        let nfa = file_parser.build_nfa();
-        if let Err(error) = nfa
-        {
-            println!("{}", format!("Error: {}", error.to_string()).red());
-            return;
+    // Report the conflicts that only exist because of LALR(1) merging separately
+    let merge_conflicts: Vec<&Conflict> = conflicts.iter().filter(|conflict| conflict.introduced_by_merge).collect();
+    if !merge_conflicts.is_empty() {
+        println!("{}", "The following conflicts were introduced by merging LR(1) states with the same core. Use --table lr1 to avoid them.".yellow());
+        for conflict in merge_conflicts {
+            has_errors |= report_conflict(conflict);
         }
-        let nfa = nfa.unwrap();
-        //println!("{:?}", map);
-        let dfa = dfa_builder::DFABuilder::convert_nfa_to_dfa(nfa);
-
-        // Generate table dfa
-        let mut table_builder = TableDFABuilder {
-            mapping: HashMap::new(),
-            node_counter: 1,
-        };
+    }
+    return has_errors;
+}
 
-        let table = table_builder.build_table_dfa(dfa);
+fn main() {
 
-        // Set emptiness info for terminal symbols
-        let terminals = file_parser.get_terminals();
-        println!("terminals: {:#?}", terminals);
+    let args = Args::parse();
 
-        // Create grammar generator
-        let mut grammar_gen = grammar2::GrammarGenerator::new(file_parser.get_terminals());
-     
-        for rule in file_parser.grammar_rules {
-            println!("Rule: {}", rule.name);
-            for item in &rule.productions {
-                for sym in &(**item).prod {
-                    print!("{}({:?}) ", sym.name, sym.emptiness);
+    let generated = match Generator::from_file(&args.filename).table(args.table).lexer_mode(args.lexer).transition_style(args.transitions).generate() {
+        Ok(value) => value,
+        Err(GeneratorError::Conflicts(conflicts)) => {
+            report_conflicts(&conflicts);
+            std::process::exit(1);
+        },
+        Err(error) => {
+            println!("{}", format!("Error: {}", error.to_string()).red());
+            std::process::exit(1);
+        },
+    };
+    // Only resolved conflicts are left, which are warnings
+    report_conflicts(generated.conflicts());
+
+    match PathBuf::from_str(&args.output) {
+        Ok(path) => {
+            if path.exists() {
+                println!("{}", format!("Error: The path {} already exists. Please delete it then try again.", path.to_str().unwrap()).red());
+                std::process::exit(1);
+            }
+            else {
+                if let Err(error) = generated.write_to_file(path.to_str().unwrap())
+                {
+                    println!("{}", format!("Error: {}", error.to_string()).red());
+                    std::process::exit(1);
                 }
             }
-            println!();
-            let symbol = grammar2::Symbol { name: rule.name.to_string(), is_terminal: false, emptiness: crate::grammar2::Empty::NonEmpty };
-            
-            grammar_gen.add_rule(symbol, rule);
-        }
-        let cc = grammar_gen.build_cannocial_collection();
-
-        // Print cc
-        /*for (set, info) in cc {
-            println!("Item {}", info.id);
-            for item in &set.set {
-                println!("{}", **item);
-            }   
-        }*/
-
-        // Fill table
-        grammar_gen.build_table(&cc);
-
-        /*let one = Symbol {
-            name: "number".to_string(),
-            is_terminal: true,
-            emptiness: grammar2::Empty::NonEmpty,
-        };
-        let space = Symbol {
-            name: "space".to_string(),
-            is_terminal: true,
-            emptiness: grammar2::Empty::PossiblyEmpty,
-        };
-        let plus = Symbol {
-            name: "plus".to_string(),
-            is_terminal: true,
-            emptiness: grammar2::Empty::NonEmpty,
-        };
-        let symbols = vec![one.clone(), plus, one, crate::grammar2::Symbol::eof_symbol()];
-        println!("{}", grammar_gen.parse(&symbols));*/
-
-        // Code gen
-        let mut code_gen = CodeGen {
-            table: table,
-            curr_state_name: "curr".to_string(),
-            grammar_gen: grammar_gen,
-        };
 
-        //let path = std::path::Path::
-
-        match PathBuf::from_str(&args.output) {
-            Ok(path) => {
-                if path.exists() {
-                    println!("{}", format!("Error: The path {} already exists. Please delete it then try again.", path.to_str().unwrap()).red());
-                }
-                else {
-                    if let Err(error) = code_gen.generate_lexer(path.to_str().unwrap())
-                    {
-                        println!("{}", format!("Error: {}", error.to_string()).red());
-                    }
-                }
-                
-            },
-            // This case is considered infalliable
-            Err(_) => (),
-        }
+        },
+        // This case is considered infalliable
+        Err(_) => (),
     }
 }
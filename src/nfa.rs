@@ -1,8 +1,10 @@
-use std::{rc::Rc, sync::Mutex, collections::{VecDeque, HashSet, HashMap}, error::Error};
+use std::{rc::Rc, sync::Mutex, collections::{HashSet, HashMap}, error::Error};
 
-use crate::{file_parser::Rule, regex_parser::{RegExParser, RegExParserError}, NFABuilder, token::Token, nfa_builder::NFABuilderError, grammar2::Empty};
+use crate::{file_parser::Rule, regex_parser::{RegExParser, RegExParserError}, nfa_builder::{NFABuilder, NFABuilderError}, grammar2::Empty};
+#[cfg(test)]
+use {std::collections::VecDeque, crate::token::Token};
 
-#[derive(Eq,PartialEq, Debug, Hash, Clone)]
+#[derive(Eq,PartialEq, Debug, Hash, Clone, PartialOrd, Ord)]
 pub enum TransitionKind {
     Empty,
     StrictEmpty,
@@ -15,6 +17,8 @@ pub struct Transition {
     pub destination: Rc<Mutex<NFANode>>,
     pub kind: TransitionKind,
     pub priority: i32,
+    // Only shown in the Debug output when tracing the automaton
+    #[allow(dead_code)]
     pub id: i32,
 }
 
@@ -23,19 +27,21 @@ pub enum NFANodeKind {
     Start,
     End,
     EndWithToken(String),
-    Regular,
     Intersection,
 }
 
 #[derive(Debug)]
 pub struct NFANode {
     pub kind: NFANodeKind,
+    // Only shown in the Debug output when tracing the automaton
+    #[allow(dead_code)]
     pub data: String,
     pub transitions: Vec<Transition>,
     pub id: i32,
     pub emptiness: crate::grammar2::Empty,
 }
 
+#[cfg(test)]
 #[derive(Debug)]
 pub struct NFASimState {
     pub destination: Rc<Mutex<NFANode>>,
@@ -56,12 +62,6 @@ impl Transition {
         counter += 1;
         return Transition {destination, kind, priority, id: counter - 1};
     }
-
-    pub fn copy(&self) -> Transition
-    {
-        return Transition {destination: Rc::clone(&self.destination), kind: self.kind.clone(),
-            priority: self.priority, id: self.id};
-    }
 }
 
 impl NFANode {
@@ -72,10 +72,6 @@ impl NFANode {
         NFANode { kind: kind, data: data, transitions: Vec::new(), id: COUNTER, emptiness: crate::grammar2::Empty::NonEmpty}
     }
 
-    pub unsafe fn new_regular(data: String) -> NFANode {
-        NFANode::new(NFANodeKind::Regular, data)
-    }
-
     pub unsafe fn new_start() -> NFANode {
         NFANode::new(NFANodeKind::Start, "Start".to_string())
     }
@@ -89,6 +85,7 @@ impl NFANode {
         self.transitions.push(Transition::new(destination, transition_kind, priority));
     }
 
+    #[cfg(test)]
     pub fn simulate(node: Rc<Mutex<NFANode>>, chars: &Vec<char>, index: usize) -> bool {
 
         let mut stack: VecDeque<(Rc<Mutex<NFANode>>, usize)> = VecDeque::new();
@@ -127,6 +124,7 @@ impl NFANode {
         return false;
     }
 
+    #[cfg(test)]
     pub fn simulate_and_get_all_tokens(node: Rc<Mutex<NFANode>>, chars: &Vec<char>, index: usize) -> (bool, Vec<Token>) {
         let mut tokens: Vec<Token> = Vec::new();
         let mut success = false;
@@ -195,11 +193,13 @@ impl NFANode {
 }
 
 impl NFA {
+    #[cfg(test)]
     pub fn simulate(&self, string: &str) -> bool {
         let chars: Vec<char> = string.chars().collect();
         return NFANode::simulate(Rc::clone(&self.start), &chars, 0);
     }
 
+    #[cfg(test)]
     pub fn simulate_and_get_token(&self, string: &str) -> (bool, Vec<Token>) {
         let chars: Vec<char> = string.chars().collect();
         return NFANode::simulate_and_get_all_tokens(Rc::clone(&self.start), &chars, 0);
@@ -6,7 +6,6 @@ use crate::{nfa::{NFA, NFANode, Transition, TransitionKind, NFANodeKind}, node::
 pub enum NFABuilderError 
 {
     NoRules,
-    RegExError,
     DuplicateNamedRule,
     NoChildren,
     UnexpectedNodeKind,
@@ -27,7 +26,6 @@ impl Error for NFABuilderError
     fn description(&self) -> &str {
         return match self {
             Self::NoRules => "There is no rules for which to generate an NFA.",
-            Self::RegExError => "There was an error parsing the regex patter.",
             Self::DuplicateNamedRule => "Names for rules must be unique. There are at least two rules with the same name.",
             Self::NoChildren => "The node has 0 children and while at least one child was expected.",
             Self::UnexpectedNodeKind => "A parser node of an unexpected type was encountered.",
@@ -112,10 +110,6 @@ impl NFABuilder {
         return Err(NFABuilderError::NoChildren);
     }
 
-    unsafe fn build_from_high(node: &Node) -> Result<NFA, NFABuilderError> {
-        return NFABuilder::build_or_of_child_nodes(node);
-    }
-
     pub unsafe fn build_from_middle(node: &Node) -> Result<NFA, NFABuilderError> {
         return NFABuilder::build(&node.children[0]);
     }
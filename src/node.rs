@@ -1,7 +1,6 @@
 use crate::node_kind::NodeKind;
 
 pub struct Node {
-    pub parent: Option<Box<Node>>,
     pub children: Vec<Box<Node>>,
     pub data: String,
     pub kind: NodeKind,
@@ -10,7 +9,6 @@ pub struct Node {
 impl Node {
     pub fn new(data: String, kind: NodeKind) -> Self {
         Self {
-            parent: None,
             children: Vec::new(),
             data: data,
             kind,
@@ -20,12 +18,4 @@ impl Node {
     pub fn add_child(&mut self, node: Box<Node>) {
         self.children.push(node);
     }
-
-    pub fn print(&self) {
-        print!("{}-", self.data);
-        for child in &self.children {
-            child.print();
-        }
-        println!("");
-    }
 }
\ No newline at end of file
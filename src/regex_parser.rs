@@ -344,7 +344,13 @@ impl RegExParser<'_> {
         let to_return;
         if self.current_char == '\\' {
             if let Some(next) = self.peek_next_character() {
-                to_return = next;
+                // \n, \t and \r stand for the characters that cannot be written in a rule
+                to_return = match next {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    _ => next,
+                };
                 self.advance();
             } else {
                 return Err(RegExParserError::EscapeNotFollowedByCharacter);
@@ -368,6 +374,18 @@ impl RegExParser<'_> {
         self.position += 1;
     }
 
+    // Creates a pattern that matches the given text literally
+    pub fn escape_literal(literal: &str) -> String {
+        let mut pattern = String::new();
+        for c in literal.chars() {
+            if RegExParser::does_char_require_escape(c) {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        return pattern;
+    }
+
     fn does_char_require_escape(character: char) -> bool {
         return match character {
             '+' | '*' | '?' | '-' | '(' | ')' | '.' | '[' | ']' | '|' | '\\' | '"' => true,
@@ -1,10 +1,12 @@
-use std::{collections::{HashMap, HashSet}};
+use std::{collections::{BTreeMap, HashMap, HashSet}, str::FromStr};
 
 use crate::{nfa::TransitionKind, dfa_builder::{DFANode, DFANodeKind}};
 
 pub struct TableDFA {
     pub transitions: HashMap<i32, HashMap<TransitionKind, i32>>,
     pub accepting_states: HashMap<i32, String>,
+    // Maps accepting states to the priority of the rule they accept
+    pub accepting_rules: HashMap<i32, i32>,
 }
 
 impl TableDFA {
@@ -13,8 +15,221 @@ impl TableDFA {
         TableDFA {
             transitions: HashMap::new(),
             accepting_states: HashMap::new(),
+            accepting_rules: HashMap::new(),
         }
     }
+
+    // Merges the states that accept the same rule and go to merged states on the same transitions.
+    // The start state keeps the id 1.
+    pub fn minimize(&self) -> TableDFA
+    {
+        let mut states: Vec<i32> = self.transitions.keys()
+            .chain(self.transitions.values().flat_map(|transitions| transitions.values()))
+            .chain(self.accepting_states.keys())
+            .cloned()
+            .collect();
+        states.push(1);
+        states.sort();
+        states.dedup();
+
+        // States start in groups by the rule they accept and are split until the groups stop changing
+        let mut groups: HashMap<i32, usize> = HashMap::new();
+        let mut group_count = 0;
+        loop {
+            let mut signatures: HashMap<(Option<i32>, usize, Vec<(TransitionKind, usize)>), usize> = HashMap::new();
+            let mut next_groups: HashMap<i32, usize> = HashMap::new();
+            for state in &states {
+                let mut transitions: Vec<(TransitionKind, usize)> = match self.transitions.get(state) {
+                    Some(value) => value.iter().map(|(kind, dest)| (kind.clone(), *groups.get(dest).unwrap_or(&0))).collect(),
+                    None => Vec::new(),
+                };
+                transitions.sort();
+                let signature = (self.accepting_rules.get(state).cloned(), *groups.get(state).unwrap_or(&0), transitions);
+                let count = signatures.len();
+                let group = *signatures.entry(signature).or_insert(count);
+                next_groups.insert(*state, group);
+            }
+
+            let done = signatures.len() == group_count;
+            group_count = signatures.len();
+            groups = next_groups;
+            if done {
+                break;
+            }
+        }
+
+        // Number the groups in the order of their first state so the start state is 1
+        let mut ids: HashMap<usize, i32> = HashMap::new();
+        for state in &states {
+            let count = ids.len() as i32;
+            ids.entry(groups[state]).or_insert(count + 1);
+        }
+
+        let mut table = TableDFA::new();
+        for (state, transitions) in &self.transitions {
+            let id = ids[&groups[state]];
+            let merged = transitions.iter().map(|(kind, dest)| (kind.clone(), ids[&groups[dest]])).collect();
+            table.transitions.insert(id, merged);
+        }
+        for (state, name) in &self.accepting_states {
+            let id = ids[&groups[state]];
+            table.accepting_states.insert(id, name.to_string());
+            table.accepting_rules.insert(id, self.accepting_rules[state]);
+        }
+        return table;
+    }
+}
+
+// The kind of input that the transitions of the generated lexer read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LexerMode {
+    Chars,
+    // The UTF-8 bytes of the input, so the lexer does not decode characters
+    Bytes,
+}
+
+impl FromStr for LexerMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_lowercase().as_str() {
+            "chars" => Ok(LexerMode::Chars),
+            "bytes" => Ok(LexerMode::Bytes),
+            _ => Err(format!("'{}' is not a lexer mode. Use chars or bytes.", s)),
+        };
+    }
+}
+
+// How the generated lexer finds the next state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionStyle {
+    // Arrays indexed by the state and the class of the character
+    Table,
+    // An if or match branch for each transition, which is readable for tiny lexers
+    Branches,
+}
+
+impl FromStr for TransitionStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.to_lowercase().as_str() {
+            "table" => Ok(TransitionStyle::Table),
+            "branches" => Ok(TransitionStyle::Branches),
+            _ => Err(format!("'{}' is not a transition style. Use table or branches.", s)),
+        };
+    }
+}
+
+// A DFA with transitions on the UTF-8 bytes of characters
+pub struct ByteTableDFA {
+    pub transitions: HashMap<i32, BTreeMap<u8, i32>>,
+    pub accepting_states: HashMap<i32, String>,
+    pub accepting_rules: HashMap<i32, i32>,
+}
+
+impl TableDFA {
+    // The states of the DFA keep their ids and the states in the middle of a character get new ids
+    pub fn to_byte_dfa(&self) -> ByteTableDFA
+    {
+        let mut builder = ByteDFABuilder {
+            dfa: ByteTableDFA {
+                transitions: HashMap::new(),
+                accepting_states: self.accepting_states.clone(),
+                accepting_rules: self.accepting_rules.clone(),
+            },
+            next_id: self.transitions.keys().chain(self.accepting_states.keys()).max().unwrap_or(&0) + 1,
+            continuations: HashMap::new(),
+        };
+
+        for (state, transitions) in &self.transitions {
+            let mut chars = Vec::new();
+            let mut any_char = None;
+            for (kind, dest) in transitions {
+                match kind {
+                    TransitionKind::Character(c) => chars.push((c.to_string().into_bytes(), *dest)),
+                    TransitionKind::AnyChar => any_char = Some(*dest),
+                    _ => (),
+                }
+            }
+            builder.add_state(*state, 0, chars, any_char);
+        }
+        return builder.dfa;
+    }
+}
+
+struct ByteDFABuilder {
+    dfa: ByteTableDFA,
+    next_id: i32,
+    // The state that reads the given number of continuation bytes before going to a state
+    continuations: HashMap<(i32, usize), i32>,
+}
+
+impl ByteDFABuilder {
+    // Adds the transitions of a state that has read the first prefix_len bytes of the characters
+    fn add_state(&mut self, id: i32, prefix_len: usize, chars: Vec<(Vec<u8>, i32)>, any_char: Option<i32>)
+    {
+        let char_len = chars.first().map(|(bytes, _)| bytes.len());
+
+        // Characters with the same next byte share a state
+        let mut groups: BTreeMap<u8, Vec<(Vec<u8>, i32)>> = BTreeMap::new();
+        for (bytes, dest) in chars {
+            groups.entry(bytes[prefix_len]).or_default().push((bytes, dest));
+        }
+
+        let mut transitions = BTreeMap::new();
+        for (byte, group) in groups {
+            if group[0].0.len() == prefix_len + 1 {
+                transitions.insert(byte, group[0].1);
+            } else {
+                let child = self.new_state();
+                transitions.insert(byte, child);
+                self.add_state(child, prefix_len + 1, group, any_char);
+            }
+        }
+
+        // Any other character goes to the destination of the wildcard
+        if let Some(dest) = any_char {
+            let ranges = match char_len {
+                Some(len) if prefix_len > 0 => vec![(0x80..=0xBF, len - prefix_len - 1)],
+                _ => vec![(0x00..=0x7F, 0), (0xC2..=0xDF, 1), (0xE0..=0xEF, 2), (0xF0..=0xF4, 3)],
+            };
+            for (range, remaining) in ranges {
+                for byte in range {
+                    if !transitions.contains_key(&byte) {
+                        let state = self.continuation(dest, remaining);
+                        transitions.insert(byte, state);
+                    }
+                }
+            }
+        }
+
+        if !transitions.is_empty() {
+            self.dfa.transitions.insert(id, transitions);
+        }
+    }
+
+    fn continuation(&mut self, dest: i32, remaining: usize) -> i32
+    {
+        if remaining == 0 {
+            return dest;
+        }
+        if let Some(state) = self.continuations.get(&(dest, remaining)) {
+            return *state;
+        }
+
+        let state = self.new_state();
+        self.continuations.insert((dest, remaining), state);
+        let next = self.continuation(dest, remaining - 1);
+        self.dfa.transitions.insert(state, (0x80..=0xBF).map(|byte| (byte, next)).collect());
+        return state;
+    }
+
+    fn new_state(&mut self) -> i32
+    {
+        self.next_id += 1;
+        return self.next_id - 1;
+    }
 }
 
 pub struct TableDFABuilder {
@@ -43,8 +258,9 @@ impl TableDFABuilder {
             let id: i32 = self.get_node_id(node);
 
             // Check if this is an accepting node
-            if let DFANodeKind::Accept(token) = &(*node).kind {
+            if let DFANodeKind::Accept(token, priority) = &(*node).kind {
                 table.accepting_states.insert(id, token.to_string());
+                table.accepting_rules.insert(id, *priority);
             }
 
             for (trans_kind, dest) in &(*node).raw_transitions
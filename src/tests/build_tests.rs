@@ -0,0 +1,27 @@
+use crate::{build, generator::GeneratorError, grammar2::TableKind, file_parser::FileParserErrorKind};
+
+fn resource_path(filename: &str) -> String
+{
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/build_tests_resources/");
+    return std::path::Path::new(path).join(filename).to_str().unwrap().to_string();
+}
+
+#[test]
+fn test_compile()
+{
+    let out_dir = std::env::temp_dir().join(format!("rlpg_build_tests_{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    // The module is named after the spec
+    let output = build::compile_to(&resource_path("calculator.rlpg"), &out_dir, TableKind::LALR1).unwrap();
+    assert!(output == out_dir.join("calculator.rs"));
+    let source = std::fs::read_to_string(&output).unwrap();
+    assert!(source.contains("pub fn parse_value("));
+    // The module is included in another crate, which has its own main function
+    assert!(!source.contains("fn main"));
+
+    let result = build::compile_to(&resource_path("duplicate_name.rlpg"), &out_dir, TableKind::LR1);
+    assert!(matches!(result, Err(GeneratorError::Spec(error)) if error.kind == FileParserErrorKind::DuplicateName));
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
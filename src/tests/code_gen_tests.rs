@@ -0,0 +1,15 @@
+use crate::code_gen::CodeGen;
+
+#[test]
+fn test_replace_value_references()
+{
+    assert_eq!(CodeGen::replace_value_references("$$ = $1 + $3;"), "__result = __arg1 + __arg3;");
+
+    // Literals are not changed
+    assert_eq!(CodeGen::replace_value_references("println!(\"$1 $$\", $1);"), "println!(\"$1 $$\", __arg1);");
+    assert_eq!(CodeGen::replace_value_references("let s = \"\\\"$1\"; $$ = $2;"), "let s = \"\\\"$1\"; __result = __arg2;");
+    assert_eq!(CodeGen::replace_value_references("$$ = if c == '$' || c == '\\'' { $1 } else { $2 };"), "__result = if c == '$' || c == '\\'' { __arg1 } else { __arg2 };");
+
+    // A lifetime is not a char literal
+    assert_eq!(CodeGen::replace_value_references("let s: &'static str = $1; $$ = $2;"), "let s: &'static str = __arg1; __result = __arg2;");
+}
@@ -1,13 +1,14 @@
-use crate::{file_parser::{FileParserErrorKind, FileParser, FileParserError, Rule}, NFA, dfa_builder::DFABuilder, dfa_simulator::DFASimulator};
+use crate::{file_parser::{FileParserErrorKind, FileParser, FileParserError}, dfa_builder::DFABuilder, dfa_simulator::DFASimulator};
 
-fn file_parse(filename: &str) -> Result<Vec<Rule>, FileParserError>
+fn file_parse(filename: &str) -> Result<FileParser, FileParserError>
 {
-    let path = "/Users/abdullah/Developer/rlpg/src/tests/file_parser_tests_resources/";
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/file_parser_tests_resources/");
     let file_path = std::path::Path::new(path).join(filename);
 
     let mut parser = FileParser::new();
 
-    return parser.parse_file(file_path.as_path().to_str().unwrap());
+    let result = parser.parse_file(file_path.as_path().to_str().unwrap());
+    return result.map(|_| parser);
 }
 
 fn assert_file_parse_success(filename: &str)
@@ -33,9 +34,9 @@ fn assert_file_parse_failure(filename: &str, error_kind: FileParserErrorKind)
 
 fn assert_regex_build_failure(filename: &str)
 {
-    let result = file_parse(filename);
+    let mut parser = file_parse(filename).unwrap();
     unsafe {
-        let build_result = NFA::build_from_rules(&result.unwrap());
+        let build_result = parser.build_nfa();
         assert!(build_result.is_err());
     }
 }
@@ -45,7 +46,7 @@ unsafe fn assert_regex(filename: &str, to_produce_token: &Vec<&str>, to_not_prod
     let result = file_parse(filename);
     assert!(!result.is_err());
     unsafe {
-        let build_result = NFA::build_from_rules(&result.unwrap());
+        let build_result = result.unwrap().build_nfa();
         assert!(!build_result.is_err());
         let nfa = build_result.unwrap();
 
@@ -124,6 +125,17 @@ fn test_section_header()
    
 }
 
+#[test]
+fn test_code_section()
+{
+    assert_file_parse_success("code_section.txt");
+    assert_file_parse_failure("code_section_without_lexer_section.txt", FileParserErrorKind::NoLexerSection);
+
+    // The code section must come before the lexer section
+    assert_file_parse_failure("code_section_after_lexer_section.txt", FileParserErrorKind::MisplacedCodeSection);
+    assert_file_parse_failure("code_section_after_grammar_section.txt", FileParserErrorKind::MisplacedCodeSection);
+}
+
 #[test]
 fn test_empty_rules()
 {
@@ -243,6 +255,9 @@ fn test_productions_with_new_symbols()
     assert_file_parse_failure("grammar_tests/prod_with_new_symbol1.txt", FileParserErrorKind::UnknownSymbol);
     assert_file_parse_failure("grammar_tests/prod_with_new_symbol2.txt", FileParserErrorKind::UnknownSymbol);
     assert_file_parse_failure("grammar_tests/prod_with_new_symbol3.txt", FileParserErrorKind::UnknownSymbol);
+
+    // Rules can be used before they are defined
+    assert_file_parse_success("grammar_tests/prod_with_later_rule.txt");
 }
 
 
@@ -263,7 +278,9 @@ fn test_rule_formatting()
     assert_file_parse_failure("grammar_tests/text_before_begin_symbol.txt", FileParserErrorKind::InvalidProduction);
     assert_file_parse_failure("grammar_tests/whitespace_before_begin_symbol.txt", FileParserErrorKind::InvalidProduction);
 
-    assert_file_parse_failure("grammar_tests/empty_production.txt", FileParserErrorKind::InvalidProduction);
+    // Empty productions are allowed
+    assert_file_parse_success("grammar_tests/empty_production.txt");
+    assert_file_parse_success("grammar_tests/empty_first_production.txt");
 
     // Space before/after colon
     assert_file_parse_failure("grammar_tests/whitespace_before_colon.txt", FileParserErrorKind::InvalidGrammarRule);
@@ -315,4 +332,165 @@ fn test_rule_named_eof_fails()
 {
     // Test ensure file parse fails if root rule does not exist
     assert_file_parse_failure("grammar_tests/rule_named_eof.txt", FileParserErrorKind::InvalidIdentifier);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_production_action_code()
+{
+    assert_file_parse_success("grammar_tests/production_action_code.txt");
+    assert_file_parse_failure("grammar_tests/invalid_production_action_code.txt", FileParserErrorKind::InvalidActionCode);
+
+    // Productions that only differ in their action code are still duplicates
+    assert_file_parse_failure("grammar_tests/duplicate_production_with_action.txt", FileParserErrorKind::DuplicateProduction);
+}
+
+#[test]
+fn test_type_declarations()
+{
+    assert_file_parse_success("grammar_tests/type_declaration.txt");
+
+    assert_file_parse_failure("grammar_tests/type_declaration_missing_brackets.txt", FileParserErrorKind::InvalidDeclaration);
+    assert_file_parse_failure("grammar_tests/type_declaration_missing_names.txt", FileParserErrorKind::InvalidDeclaration);
+    assert_file_parse_failure("grammar_tests/unknown_declaration.txt", FileParserErrorKind::InvalidDeclaration);
+
+    // Only grammar rules can have a type
+    assert_file_parse_failure("grammar_tests/type_declaration_for_terminal.txt", FileParserErrorKind::InvalidDeclaration);
+    assert_file_parse_failure("grammar_tests/type_declaration_unknown_rule.txt", FileParserErrorKind::UnknownSymbol);
+
+    // The > of an arrow does not close the type
+    let mut parser = FileParser::new();
+    assert!(parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\n%type <Box<dyn Fn(i32) -> i32>> root\nroot: number\n;\n").is_ok());
+    assert!(parser.value_types["root"] == "Box<dyn Fn(i32) -> i32>");
+}
+
+#[test]
+fn test_string_literals()
+{
+    assert_file_parse_success("grammar_tests/string_literal.txt");
+    assert_file_parse_failure("grammar_tests/unterminated_string_literal.txt", FileParserErrorKind::InvalidStringLiteral);
+    assert_file_parse_failure("grammar_tests/empty_string_literal.txt", FileParserErrorKind::InvalidStringLiteral);
+}
+
+#[test]
+fn test_precedence_declarations()
+{
+    assert_file_parse_success("grammar_tests/precedence_declaration.txt");
+    assert_file_parse_failure("grammar_tests/precedence_missing_names.txt", FileParserErrorKind::InvalidDeclaration);
+    assert_file_parse_failure("grammar_tests/precedence_declared_twice.txt", FileParserErrorKind::InvalidDeclaration);
+
+    // Only terminals can have a precedence
+    assert_file_parse_failure("grammar_tests/precedence_for_rule.txt", FileParserErrorKind::InvalidDeclaration);
+
+    // %prec must name a declared precedence and end the production
+    assert_file_parse_failure("grammar_tests/prec_undeclared.txt", FileParserErrorKind::UnknownSymbol);
+    assert_file_parse_failure("grammar_tests/prec_followed_by_symbol.txt", FileParserErrorKind::InvalidProduction);
+}
+
+#[test]
+fn test_parse_from_str_and_reader()
+{
+    let source = "SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: number \"+\" number\n;\n";
+
+    let mut parser = FileParser::new();
+    assert!(parser.parse_str(source).is_ok());
+    assert!(parser.grammar_rules.len() == 1);
+
+    let mut parser = FileParser::new();
+    assert!(parser.parse_reader(std::io::Cursor::new(source)).is_ok());
+    assert!(parser.grammar_rules.len() == 1);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::NoGrammarSection);
+}
+
+#[test]
+fn test_error_lines()
+{
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nnumber [a-z]+\nSECTION GRAMMAR\nroot: number\n;\n");
+    assert!(result.err().unwrap().line == Some(3));
+
+    // Unknown symbols are reported on the line where they are first used
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: number\n| missing\n;\nother: missing\n;\n");
+    let error = result.err().unwrap();
+    assert!(error.kind == FileParserErrorKind::UnknownSymbol);
+    assert!(error.line == Some(5));
+
+    // Errors in %type declarations point at the declaration
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\n%type <u32> number\nroot: number\n;\n");
+    let error = result.err().unwrap();
+    assert!(error.kind == FileParserErrorKind::InvalidDeclaration);
+    assert!(error.line == Some(4));
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: number\n;\n%type <u32\n");
+    let error = result.err().unwrap();
+    assert!(error.kind == FileParserErrorKind::InvalidDeclaration);
+    assert!(error.line == Some(6));
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_file("missing_file.txt");
+    assert!(result.err().unwrap().line == None);
+}
+
+#[test]
+fn test_reserved_error_name()
+{
+    // error can be used in productions without a lexer rule
+    let mut parser = FileParser::new();
+    assert!(parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: number\n| error\n;\n").is_ok());
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nerror [0-9]+\nSECTION GRAMMAR\nroot: error\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::InvalidRuleName);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: error\n;\nerror: number\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::InvalidIdentifier);
+}
+
+#[test]
+fn test_ast_declaration()
+{
+    let mut parser = FileParser::new();
+    assert!(parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\n%ast\nroot: number\n;\n").is_ok());
+    assert!(parser.ast);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\n%ast root\nroot: number\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::InvalidDeclaration);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\n%ast\n%ast\nroot: number\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::InvalidDeclaration);
+}
+
+#[test]
+fn test_ebnf_operators()
+{
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nword [a-z]+\nSECTION GRAMMAR\nroot: word? (number (\",\" number)*)+\n| (root) \";\"\n;\n");
+    assert!(result.is_ok());
+    let names: Vec<&str> = parser.grammar_rules.iter().map(|rule| rule.name.as_str()).collect();
+    assert_eq!(names, vec!["root", "word?", "(\",\" number)", "(\",\" number)*", "(number (\",\" number)*)", "(number (\",\" number)*)+"]);
+    let symbols = |rule: usize, prod: usize| unsafe { (&(*parser.grammar_rules[rule].productions[prod]).prod).iter().map(|symbol| symbol.name.to_string()).collect::<Vec<String>>() };
+    // (root) is the same as root
+    assert_eq!(symbols(0, 1), vec!["root", "\";\""]);
+    // x* is left recursive
+    assert_eq!(symbols(3, 1), vec!["(\",\" number)*", "(\",\" number)"]);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: (number | \",\"\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::InvalidProduction);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: number )\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::InvalidProduction);
+
+    let mut parser = FileParser::new();
+    let result = parser.parse_str("SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: (number | number)\n;\n");
+    assert!(result.err().unwrap().kind == FileParserErrorKind::DuplicateProduction);
+}
@@ -0,0 +1,122 @@
+use crate::{generator::{Generator, GeneratorError, Spec}, file_parser::FileParserErrorKind, grammar2::{Action, TableKind}, table_dfa_builder::{ByteTableDFA, LexerMode, TransitionStyle}};
+
+fn calculator_spec() -> Spec
+{
+    return Spec::new()
+        .lexer_rule("number", "[0-9]+")
+        .unnamed_rule("\\ ")
+        .declaration("%left \"+\"")
+        .declaration("%left \"*\"")
+        .grammar_rule("root", &["root \"+\" root", "root \"*\" root", "number"]);
+}
+
+#[test]
+fn test_generate_from_spec()
+{
+    let generated = Generator::from_spec(&calculator_spec()).generate().unwrap();
+    assert!(!generated.lexer_table().accepting_states.is_empty());
+    assert!(generated.action_table().values().any(|action| matches!(action, Action::Accept)));
+    assert!(!generated.goto_table().is_empty());
+
+    // The conflicts are resolved by the precedence declarations
+    assert!(!generated.conflicts().is_empty());
+    assert!(generated.conflicts().iter().all(|conflict| conflict.is_resolved()));
+
+    let source = generated.source();
+    assert!(source.contains("pub fn get_tokens("));
+    assert!(source.contains("pub fn parse("));
+
+    // The parse tables are static arrays
+    assert!(source.contains("static ACTIONS: ["));
+    assert!(source.contains("static GOTOS: ["));
+    assert!(!source.contains("HashMap"));
+}
+
+#[test]
+fn test_generate_from_text()
+{
+    let spec = "SECTION LEXER\nnumber [0-9]+\nSECTION GRAMMAR\nroot: number\n;\n";
+    let lr = Generator::from_text(spec).generate().unwrap();
+    let lalr = Generator::from_text(spec).table(TableKind::LALR1).generate().unwrap();
+    assert!(lr.action_table().len() == lalr.action_table().len());
+}
+
+#[test]
+fn test_generate_errors()
+{
+    let result = Generator::from_spec(&Spec::new().lexer_rule("number", "[0-9]+")).generate();
+    assert!(matches!(result, Err(GeneratorError::Spec(error)) if error.kind == FileParserErrorKind::NoGrammarRules));
+
+    let result = Generator::from_spec(&Spec::new().lexer_rule("number", "[0-9").grammar_rule("root", &["number"])).generate();
+    assert!(matches!(result, Err(GeneratorError::Lexer(_))));
+
+    let spec = Spec::new()
+        .lexer_rule("number", "[0-9]+")
+        .grammar_rule("root", &["root \"+\" root", "number"]);
+    let result = Generator::from_spec(&spec).generate();
+    assert!(matches!(result, Err(GeneratorError::Conflicts(conflicts)) if !conflicts.is_empty()));
+}
+
+// Follows the transitions of the byte DFA and returns the rule that accepts the whole text
+fn run_byte_dfa(dfa: &ByteTableDFA, text: &str) -> Option<String>
+{
+    let mut state = 1;
+    for byte in text.bytes() {
+        state = *dfa.transitions.get(&state)?.get(&byte)?;
+    }
+    return dfa.accepting_states.get(&state).cloned();
+}
+
+#[test]
+fn test_byte_dfa()
+{
+    let spec = Spec::new()
+        .lexer_rule("word", "[a-zé]+")
+        .lexer_rule("snow", "☃")
+        .lexer_rule("other", ".")
+        .grammar_rule("root", &["word", "snow", "other"]);
+    let generated = Generator::from_spec(&spec).lexer_mode(LexerMode::Bytes).generate().unwrap();
+    let dfa = generated.lexer_table().to_byte_dfa();
+
+    assert!(run_byte_dfa(&dfa, "café") == Some("word".to_string()));
+    assert!(run_byte_dfa(&dfa, "☃") == Some("snow".to_string()));
+
+    // Characters of every length that no other rule has go to the wildcard
+    for text in ["X", "%", "ß", "€", "😀"] {
+        assert!(run_byte_dfa(&dfa, text) == Some("other".to_string()));
+    }
+
+    // The states in the middle of a character do not accept
+    let first_byte = dfa.transitions[&1][&"€".as_bytes()[0]];
+    assert!(!dfa.accepting_states.contains_key(&first_byte));
+    assert!(dfa.transitions[&1].get(&0xFF).is_none());
+
+    let source = generated.source();
+    assert!(source.contains("fn transition(curr: i32, byte: u8)"));
+    assert!(!source.contains("TransitionKind"));
+}
+
+#[test]
+fn test_transition_styles()
+{
+    let spec = Spec::new()
+        .lexer_rule("identifier", "[a-f][a-f0-9]*")
+        .lexer_rule("number", "[0-9]+")
+        .grammar_rule("root", &["identifier", "number"]);
+
+    // Tables are the default
+    for mode in [LexerMode::Chars, LexerMode::Bytes] {
+        let source = Generator::from_spec(&spec).lexer_mode(mode).generate().unwrap().source();
+        assert!(source.contains("static TRANSITIONS"));
+        assert!(!source.contains("if curr =="));
+    }
+
+    // The states for each letter are merged, so the letters, the digits and the other characters are the only classes
+    let generated = Generator::from_spec(&spec).generate().unwrap();
+    assert!(generated.lexer_table().accepting_states.len() == 2);
+    assert!(generated.source().contains("const CLASS_COUNT: usize = 3;"));
+
+    let source = Generator::from_spec(&spec).transition_style(TransitionStyle::Branches).generate().unwrap().source();
+    assert!(source.contains("if curr =="));
+    assert!(!source.contains("static TRANSITIONS"));
+}
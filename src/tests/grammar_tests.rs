@@ -0,0 +1,271 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{file_parser::FileParser, grammar2::{Action, ConflictKind, Empty, GrammarGenerator, GrammarSet, GrammarSetInfo, Resolution, Symbol, TableKind}};
+
+unsafe fn build_grammar(filename: &str, kind: TableKind) -> (GrammarGenerator, HashMap<GrammarSet, GrammarSetInfo>)
+{
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/grammar_tests_resources/");
+    let file_path = std::path::Path::new(path).join(filename);
+
+    let mut parser = FileParser::new();
+    parser.parse_file(file_path.to_str().unwrap()).unwrap();
+    parser.build_nfa().unwrap();
+
+    let mut grammar_gen = GrammarGenerator::new(parser.get_terminals());
+    grammar_gen.precedences = parser.precedences.clone();
+    for rule in parser.grammar_rules {
+        let symbol = Symbol::new(rule.name.to_string(), false, Empty::NonEmpty);
+        grammar_gen.add_rule(symbol, rule);
+    }
+    let cc = grammar_gen.build_collection(kind);
+    grammar_gen.build_table(&cc);
+    return (grammar_gen, cc);
+}
+
+fn terminal(name: &str) -> Symbol
+{
+    return Symbol::new(name.to_string(), true, Empty::NonEmpty);
+}
+
+// Gets the actions of every state where the production is completed with the lookahead
+unsafe fn get_reduce_state_actions<'a>(grammar_gen: &'a GrammarGenerator, cc: &HashMap<GrammarSet, GrammarSetInfo>, production_id: usize, lookahead: &str) -> Vec<Option<&'a Action>>
+{
+    let mut actions = Vec::new();
+    for (set, info) in cc {
+        for item in &set.set {
+            let production = &*(**item).production;
+            if production.id == production_id && (**item).placeholder_index == production.prod.len() && (**item).lookup_sym.name == lookahead {
+                actions.push(grammar_gen.action_table.get(&(info.id, terminal(lookahead))));
+            }
+        }
+    }
+    assert!(!actions.is_empty());
+    return actions;
+}
+
+unsafe fn assert_shift(grammar_gen: &GrammarGenerator, cc: &HashMap<GrammarSet, GrammarSetInfo>, production_id: usize, lookahead: &str)
+{
+    for action in get_reduce_state_actions(grammar_gen, cc, production_id, lookahead) {
+        assert!(matches!(action, Some(Action::Shift(_))));
+    }
+}
+
+unsafe fn assert_reduce(grammar_gen: &GrammarGenerator, cc: &HashMap<GrammarSet, GrammarSetInfo>, production_id: usize, lookahead: &str)
+{
+    for action in get_reduce_state_actions(grammar_gen, cc, production_id, lookahead) {
+        assert!(matches!(action, Some(Action::Reduce(_, _, id)) if *id == production_id));
+    }
+}
+
+#[test]
+fn test_associativity()
+{
+    unsafe {
+        let (mut grammar_gen, cc) = build_grammar("associativity.txt", TableKind::LR1);
+
+        // root: root "+" root is left associative
+        assert_reduce(&grammar_gen, &cc, 0, "\"+\"");
+        // root: root "^" root is right associative
+        assert_shift(&grammar_gen, &cc, 1, "\"^\"");
+
+        // root: root "<" root is not associative
+        for action in get_reduce_state_actions(&grammar_gen, &cc, 2, "\"<\"") {
+            assert!(action.is_none());
+        }
+        let number = terminal("number");
+        let less = terminal("\"<\"");
+        assert!(grammar_gen.parse(&vec![number.clone(), less.clone(), number.clone(), Symbol::eof_symbol()]));
+        assert!(!grammar_gen.parse(&vec![number.clone(), less.clone(), number.clone(), less, number, Symbol::eof_symbol()]));
+    }
+}
+
+#[test]
+fn test_precedence()
+{
+    unsafe {
+        let (grammar_gen, cc) = build_grammar("precedence.txt", TableKind::LR1);
+
+        // "*" has a higher precedence than "+" and "-"
+        assert_shift(&grammar_gen, &cc, 0, "\"*\"");
+        assert_shift(&grammar_gen, &cc, 1, "\"*\"");
+        assert_reduce(&grammar_gen, &cc, 2, "\"+\"");
+        assert_reduce(&grammar_gen, &cc, 2, "\"-\"");
+
+        // "+" and "-" have the same precedence
+        assert_reduce(&grammar_gen, &cc, 0, "\"-\"");
+        assert_reduce(&grammar_gen, &cc, 1, "\"+\"");
+
+        // The negation takes the precedence of NEG instead of "-"
+        assert_reduce(&grammar_gen, &cc, 3, "\"*\"");
+    }
+}
+
+#[test]
+fn test_shift_reduce_conflict()
+{
+    unsafe {
+        let (grammar_gen, cc) = build_grammar("ambiguous.txt", TableKind::LR1);
+        assert!(!grammar_gen.conflicts.is_empty());
+        for conflict in &grammar_gen.conflicts {
+            assert!(conflict.kind == ConflictKind::ShiftReduce);
+            assert!(!conflict.is_resolved());
+            assert!(conflict.lookahead.name == "\"+\"");
+        }
+
+        // Unresolved shift/reduce conflicts shift
+        assert_shift(&grammar_gen, &cc, 0, "\"+\"");
+    }
+}
+
+#[test]
+fn test_reduce_reduce_conflict()
+{
+    unsafe {
+        let (grammar_gen, _) = build_grammar("reduce_reduce.txt", TableKind::LR1);
+        assert!(grammar_gen.conflicts.len() == 1);
+
+        let conflict = &grammar_gen.conflicts[0];
+        assert!(conflict.kind == ConflictKind::ReduceReduce);
+        assert!(!conflict.is_resolved());
+        assert!(conflict.lookahead.name == "eof");
+
+        let message = conflict.to_string();
+        assert!(message.contains("first -> number."));
+        assert!(message.contains("second -> number."));
+    }
+}
+
+#[test]
+fn test_shift_reduce_reduce_conflict()
+{
+    unsafe {
+        let (grammar_gen, cc) = build_grammar("shift_reduce_reduce.txt", TableKind::LR1);
+        assert!(grammar_gen.conflicts.len() == 2);
+
+        // The shift competes with the reduce of the production that appears first
+        let shift_reduce = &grammar_gen.conflicts[0];
+        assert!(shift_reduce.kind == ConflictKind::ShiftReduce);
+        assert!(shift_reduce.lookahead.name == "\"+\"");
+        assert!(matches!(shift_reduce.resolution, Some(Resolution::Reduce)));
+        let message = shift_reduce.to_string();
+        assert!(message.contains("first -> number."));
+        assert!(!message.contains("second -> number."));
+
+        let reduce_reduce = &grammar_gen.conflicts[1];
+        assert!(reduce_reduce.kind == ConflictKind::ReduceReduce);
+        assert!(reduce_reduce.lookahead.name == "\"+\"");
+        assert!(!reduce_reduce.is_resolved());
+        let message = reduce_reduce.to_string();
+        assert!(message.contains("first -> number."));
+        assert!(message.contains("second -> number."));
+        assert!(!message.contains("root -> number."));
+
+        // The table reduces as the report says
+        assert_reduce(&grammar_gen, &cc, 0, "\"+\"");
+    }
+}
+
+#[test]
+fn test_conflicts_resolved_by_precedence()
+{
+    unsafe {
+        let (grammar_gen, _) = build_grammar("associativity.txt", TableKind::LR1);
+        assert!(!grammar_gen.conflicts.is_empty());
+        for conflict in &grammar_gen.conflicts {
+            assert!(conflict.is_resolved());
+        }
+    }
+}
+
+#[test]
+fn test_lalr_merge_conflict()
+{
+    unsafe {
+        let (grammar_gen, _) = build_grammar("merge_conflict.txt", TableKind::LR1);
+        assert!(grammar_gen.conflicts.is_empty());
+
+        // Merging the states that reduce "c" causes reduce/reduce conflicts on "d" and "e"
+        let (grammar_gen, _) = build_grammar("merge_conflict.txt", TableKind::LALR1);
+        assert!(grammar_gen.conflicts.len() == 2);
+        for conflict in &grammar_gen.conflicts {
+            assert!(conflict.kind == ConflictKind::ReduceReduce);
+            assert!(conflict.introduced_by_merge);
+        }
+    }
+}
+
+// The items of each state as text, so that the states of different generators can be compared
+unsafe fn get_state_items(cc: &HashMap<GrammarSet, GrammarSetInfo>) -> HashSet<BTreeSet<String>>
+{
+    return cc.keys().map(|set| set.set.iter().map(|item| (**item).to_string()).collect()).collect();
+}
+
+#[test]
+fn test_lalr_lookahead_propagation()
+{
+    unsafe {
+        for filename in ["ambiguous.txt", "associativity.txt", "merge_conflict.txt", "nullable.txt", "precedence.txt", "reduce_reduce.txt", "shift_reduce_reduce.txt", "slr_conflict.txt"] {
+            // The LALR(1) states are the LR(1) states with the same core merged together
+            let (_, lr_cc) = build_grammar(filename, TableKind::LR1);
+            let mut merged: HashMap<BTreeSet<(usize, usize)>, BTreeSet<String>> = HashMap::new();
+            for set in lr_cc.keys() {
+                let core = set.set.iter().map(|item| ((*(**item).production).id, (**item).placeholder_index)).collect();
+                merged.entry(core).or_default().extend(set.set.iter().map(|item| (**item).to_string()));
+            }
+
+            let (_, lalr_cc) = build_grammar(filename, TableKind::LALR1);
+            assert!(get_state_items(&lalr_cc) == merged.into_values().collect(), "{}", filename);
+        }
+    }
+}
+
+#[test]
+fn test_table_kinds()
+{
+    unsafe {
+        let (lr_gen, lr_cc) = build_grammar("slr_conflict.txt", TableKind::LR1);
+        let (lalr_gen, lalr_cc) = build_grammar("slr_conflict.txt", TableKind::LALR1);
+        let (slr_gen, slr_cc) = build_grammar("slr_conflict.txt", TableKind::SLR1);
+        assert!(lalr_cc.len() < lr_cc.len());
+        assert!(slr_cc.len() == lalr_cc.len());
+
+        // Only SLR(1) reduces right -> left on "=" since "=" is in the follow set of right
+        assert!(lr_gen.conflicts.is_empty());
+        assert!(lalr_gen.conflicts.is_empty());
+        assert!(slr_gen.conflicts.len() == 1);
+        assert!(slr_gen.conflicts[0].kind == ConflictKind::ShiftReduce);
+        assert!(!slr_gen.conflicts[0].introduced_by_merge);
+
+        let id = terminal("id");
+        let symbols = vec![terminal("\"*\""), id.clone(), terminal("\"=\""), id, Symbol::eof_symbol()];
+        for mut grammar_gen in [lr_gen, lalr_gen] {
+            assert!(grammar_gen.parse(&symbols));
+        }
+    }
+}
+
+#[test]
+fn test_nullable_rules()
+{
+    unsafe {
+        let (mut grammar_gen, _) = build_grammar("nullable.txt", TableKind::LR1);
+        assert!(grammar_gen.conflicts.is_empty());
+
+        let rule = |name: &str| Symbol::new(name.to_string(), false, Empty::NonEmpty);
+        assert!(grammar_gen.is_nullable(&rule("optional")));
+        assert!(grammar_gen.is_nullable(&rule("list")));
+        assert!(grammar_gen.is_nullable(&rule("root")));
+        assert!(!grammar_gen.is_nullable(&rule("item")));
+
+        // The first set of item looks past the empty optional
+        let question = terminal("\"?\"");
+        let number = terminal("number");
+        assert!(grammar_gen.get_first_set(&rule("item")) == HashSet::from([question.clone(), number.clone()]));
+
+        let eof = Symbol::eof_symbol();
+        assert!(grammar_gen.parse(&vec![eof.clone()]));
+        assert!(grammar_gen.parse(&vec![number.clone(), eof.clone()]));
+        assert!(grammar_gen.parse(&vec![question.clone(), number.clone(), number.clone(), question.clone(), number.clone(), eof.clone()]));
+        assert!(!grammar_gen.parse(&vec![question.clone(), question, eof]));
+    }
+}
@@ -2,4 +2,16 @@
 mod regex_tests;
 
 #[cfg(test)]
-mod file_parser_tests;
\ No newline at end of file
+mod file_parser_tests;
+
+#[cfg(test)]
+mod grammar_tests;
+
+#[cfg(test)]
+mod generator_tests;
+
+#[cfg(test)]
+mod build_tests;
+
+#[cfg(test)]
+mod code_gen_tests;
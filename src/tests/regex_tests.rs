@@ -1,4 +1,4 @@
-use crate::{NFABuilder, regex_parser::{RegExParser, RegExParserError}, NFA, DFABuilder, dfa_simulator::DFASimulator};
+use crate::{nfa_builder::NFABuilder, regex_parser::{RegExParser, RegExParserError}, nfa::NFA, dfa_builder::DFABuilder, dfa_simulator::DFASimulator};
 
 unsafe fn assert_parse_error(regex: &str, kind: RegExParserError)
 {
@@ -244,4 +244,22 @@ fn or_tests()
         let to_reject = vec!["", "bb", "aa", "aaaa", "cc", " ", "0"];
         test_regex("a|b|c", &to_accept, &to_reject);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn escape_tests()
+{
+    unsafe
+    {
+        assert_parse_error("\\", RegExParserError::EscapeNotFollowedByCharacter);
+
+        let to_accept = vec!["+", "++"];
+        let to_reject = vec!["", "a", "\\"];
+        test_regex("\\++", &to_accept, &to_reject);
+
+        // \n, \t and \r match a newline, a tab and a carriage return
+        let to_accept = vec!["\n", "\t", "\r", " \r\n"];
+        let to_reject = vec!["", "n", "t", "r", "\\n"];
+        test_regex("[\\ \\n\\t\\r]+", &to_accept, &to_reject);
+    }
+}
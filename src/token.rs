@@ -9,9 +9,3 @@ pub struct Token {
     pub symbol: Symbol,
 }
 
-impl Token {
-    pub fn new(lexeme: String, start_col: usize, end_col: usize, symbol: Symbol) -> Token
-    {
-        Token { lexeme: lexeme, line: 0, start_col: start_col, end_col: end_col, symbol: symbol }
-    }
-}
\ No newline at end of file